@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// The path of the generated index page, without extension.
+const INDEX: &str = "index";
+
+/// One entry of the documentation, submited by a generator.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    /// The path of the page in the documentation site, without extension. Ex: `lib/parser`.
+    pub path: String,
+    /// The title of the page.
+    pub title: String,
+    /// The content of the page, in markdown.
+    pub body: String,
+    /// Links to other entries (by path) or to external URL.
+    pub links: Vec<String>,
+}
+
+/// The documentation index, aggregate the entries of all generators.
+#[derive(Debug, Default)]
+pub struct Documentation {
+    /// Entries by path, with the name of the generator that add it.
+    entries: BTreeMap<String, (String, Entry)>,
+}
+
+/// An error when a generator add an entry.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DocumentationError {
+    /// The path is empty, absolute or contains an empty, `.` or `..` segment.
+    InvalidPath(String),
+    /// The path `index`, used by the generated index page.
+    Reserved(String),
+    /// An other generator already add an entry at this path.
+    DuplicatePath { path: String, generator: String },
+}
+
+impl fmt::Display for DocumentationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath(p) => write!(f, "Invalid documentation path: {:?}", p),
+            Self::Reserved(p) => write!(
+                f,
+                "The documentation path {:?} is reserved for the index",
+                p
+            ),
+            Self::DuplicatePath { path, generator } => write!(
+                f,
+                "The documentation path {:?} is already used by the generator {:?}",
+                path, generator
+            ),
+        }
+    }
+}
+impl Error for DocumentationError {}
+
+impl Documentation {
+    /// Add an entry from the generator.
+    pub fn add(&mut self, generator: &str, entry: Entry) -> Result<(), DocumentationError> {
+        if entry.path.is_empty()
+            || entry
+                .path
+                .split('/')
+                .any(|s| s.is_empty() || s == "." || s == "..")
+        {
+            return Err(DocumentationError::InvalidPath(entry.path));
+        } else if entry.path == INDEX {
+            return Err(DocumentationError::Reserved(entry.path));
+        } else if let Some((g, _)) = self.entries.get(&entry.path) {
+            return Err(DocumentationError::DuplicatePath {
+                path: entry.path,
+                generator: g.clone(),
+            });
+        }
+        self.entries
+            .insert(entry.path.clone(), (generator.to_string(), entry));
+        Ok(())
+    }
+
+    /// Iter over all entries, sorted by path.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values().map(|(_, e)| e)
+    }
+
+    /// Write the documentation as markdown files into `dir`, with an `index.md`.
+    pub fn write_markdown(&self, dir: &Path) -> io::Result<()> {
+        self.write_site(dir, "md", |e, root| {
+            let mut s = format!("# {}\n\n{}\n", e.title, e.body.trim_end());
+            if !e.links.is_empty() {
+                s.push_str("\n## Links\n\n");
+                for l in e.links.iter() {
+                    s.push_str(&format!("- [{}]({})\n", l, link(l, root, "md")));
+                }
+            }
+            s
+        })?;
+
+        let mut index = String::from("# Documentation\n\n");
+        for e in self.entries() {
+            index.push_str(&format!("- [{}]({}.md)\n", e.title, e.path));
+        }
+        fs::write(dir.join(format!("{}.md", INDEX)), index)
+    }
+
+    /// Write the documentation as HTML files into `dir`, with an `index.html`.
+    pub fn write_html(&self, dir: &Path) -> io::Result<()> {
+        self.write_site(dir, "html", |e, root| {
+            let mut s = String::new();
+            markdown_to_html(&mut s, &e.body);
+            if !e.links.is_empty() {
+                s.push_str("<h2>Links</h2>\n<ul>\n");
+                for l in e.links.iter() {
+                    s.push_str(&format!(
+                        "<li><a href=\"{}\">{}</a></li>\n",
+                        escape(&link(l, root, "html")),
+                        escape(l)
+                    ));
+                }
+                s.push_str("</ul>\n");
+            }
+            html_page(&e.title, &format!("{}{}.html", root, INDEX), &s)
+        })?;
+
+        let mut list = String::from("<ul>\n");
+        for e in self.entries() {
+            list.push_str(&format!(
+                "<li><a href=\"{}.html\">{}</a></li>\n",
+                escape(&e.path),
+                escape(&e.title)
+            ));
+        }
+        list.push_str("</ul>\n");
+        let index = format!("{}.html", INDEX);
+        fs::write(dir.join(&index), html_page("Documentation", &index, &list))
+    }
+
+    /// Write each entry into `dir/path.extension` with the content from `render`.
+    /// The second argument of render is the relative path to the root of the site.
+    fn write_site<F>(&self, dir: &Path, extension: &str, render: F) -> io::Result<()>
+    where
+        F: Fn(&Entry, &str) -> String,
+    {
+        fs::create_dir_all(dir)?;
+        for e in self.entries() {
+            let file = dir.join(format!("{}.{}", e.path, extension));
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let root = "../".repeat(e.path.matches('/').count());
+            fs::write(file, render(e, &root))?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the href of a link, an URL is unchanged, a path is relative to the root.
+fn link(l: &str, root: &str, extension: &str) -> String {
+    if l.contains("://") {
+        l.to_string()
+    } else {
+        format!("{}{}.{}", root, l, extension)
+    }
+}
+
+fn html_page(title: &str, index: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<nav><a href=\"{1}\">Index</a></nav>\n<h1>{0}</h1>\n{2}</body>\n</html>\n",
+        escape(title),
+        escape(index),
+        body
+    )
+}
+
+/// Convert a subset of markdown (headers, paragraphs and code blocks) to HTML.
+fn markdown_to_html(out: &mut String, markdown: &str) {
+    let mut paragraph = String::new();
+    let mut code: Option<String> = None;
+
+    let end_paragraph = |out: &mut String, paragraph: &mut String| {
+        if !paragraph.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", escape(paragraph.trim_end())));
+            paragraph.clear();
+        }
+    };
+
+    for line in markdown.lines() {
+        if let Some(c) = code.as_mut() {
+            if line.trim_start().starts_with("```") {
+                out.push_str(&format!("<pre><code>{}</code></pre>\n", escape(c)));
+                code = None;
+            } else {
+                c.push_str(line);
+                c.push('\n');
+            }
+        } else if line.trim_start().starts_with("```") {
+            end_paragraph(out, &mut paragraph);
+            code = Some(String::new());
+        } else if line.trim().is_empty() {
+            end_paragraph(out, &mut paragraph);
+        } else if line.starts_with('#') {
+            end_paragraph(out, &mut paragraph);
+            let level = line.chars().take_while(|c| *c == '#').count().min(6);
+            let title = line.trim_start_matches('#').trim();
+            out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape(title)));
+        } else {
+            paragraph.push_str(line);
+            paragraph.push('\n');
+        }
+    }
+
+    if let Some(c) = code {
+        out.push_str(&format!("<pre><code>{}</code></pre>\n", escape(&c)));
+    }
+    end_paragraph(out, &mut paragraph);
+}
+
+fn escape(s: &str) -> String {
+    let mut e = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => e.push_str("&lt;"),
+            '>' => e.push_str("&gt;"),
+            '&' => e.push_str("&amp;"),
+            '"' => e.push_str("&quot;"),
+            c => e.push(c),
+        }
+    }
+    e
+}
+
+#[test]
+fn test_documentation() {
+    let mut doc = Documentation::default();
+    doc.add(
+        "rustc",
+        Entry {
+            path: "lib/parser".to_string(),
+            title: "The <parser>".to_string(),
+            body: "# Usage\n\nParse the\nfile.\n\n```\nlet t = parse();\n```\n".to_string(),
+            links: vec!["lib".to_string(), "https://exemple.com/".to_string()],
+        },
+    )
+    .unwrap();
+    doc.add(
+        "rustc",
+        Entry {
+            path: "lib".to_string(),
+            title: "Lib".to_string(),
+            body: "The library.".to_string(),
+            links: Vec::new(),
+        },
+    )
+    .unwrap();
+
+    let duplicate = Entry {
+        path: "lib".to_string(),
+        title: "Lib".to_string(),
+        body: String::new(),
+        links: Vec::new(),
+    };
+    assert_eq!(
+        Err(DocumentationError::DuplicatePath {
+            path: "lib".to_string(),
+            generator: "rustc".to_string(),
+        }),
+        doc.add("other", duplicate.clone())
+    );
+    assert_eq!(
+        Err(DocumentationError::InvalidPath("../lib".to_string())),
+        doc.add(
+            "other",
+            Entry {
+                path: "../lib".to_string(),
+                ..duplicate.clone()
+            }
+        )
+    );
+
+    assert_eq!(
+        Err(DocumentationError::Reserved("index".to_string())),
+        doc.add(
+            "other",
+            Entry {
+                path: "index".to_string(),
+                ..duplicate.clone()
+            }
+        )
+    );
+    doc.add(
+        "other",
+        Entry {
+            path: "lib/index".to_string(),
+            ..duplicate
+        },
+    )
+    .unwrap();
+
+    let dir = crate::temp::TempDir::new("documentation");
+    doc.write_markdown(&dir).unwrap();
+    doc.write_html(&dir).unwrap();
+
+    assert_eq!(
+        "# Documentation\n\n- [Lib](lib.md)\n- [Lib](lib/index.md)\n- [The <parser>](lib/parser.md)\n",
+        fs::read_to_string(dir.join("index.md")).unwrap()
+    );
+    assert_eq!(
+        "# The <parser>\n\n# Usage\n\nParse the\nfile.\n\n```\nlet t = parse();\n```\n\n## Links\n\n- [lib](../lib.md)\n- [https://exemple.com/](https://exemple.com/)\n",
+        fs::read_to_string(dir.join("lib/parser.md")).unwrap()
+    );
+
+    let html = fs::read_to_string(dir.join("lib/parser.html")).unwrap();
+    assert!(html.contains("<title>The &lt;parser&gt;</title>"));
+    assert!(html.contains("<a href=\"../index.html\">Index</a>"));
+    assert!(html.contains(
+        "<h1>Usage</h1>\n<p>Parse the\nfile.</p>\n<pre><code>let t = parse();\n</code></pre>\n"
+    ));
+    assert!(html.contains("<li><a href=\"../lib.html\">lib</a></li>"));
+    assert!(fs::read_to_string(dir.join("index.html"))
+        .unwrap()
+        .contains("<li><a href=\"lib/parser.html\">The &lt;parser&gt;</a></li>"));
+}
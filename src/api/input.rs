@@ -0,0 +1,93 @@
+use crate::configuration::Output;
+use std::{error::Error, fmt};
+
+/// The named inputs of a generator call, `("src": "src/", "headers": h) | "ld"`.
+/// Each input is the output of its object, mounted separately, a generator
+/// lists and opens them by name.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Inputs(Vec<(String, Output)>);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum InputError {
+    /// No input with this name.
+    Unknown(String),
+    /// The file is not in the directory input, the input name and the path.
+    NotFound(String, String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(n) => write!(f, "The generator has no input {:?}", n),
+            Self::NotFound(n, p) => write!(f, "The file {:?} is not in the input {:?}", p, n),
+        }
+    }
+}
+impl Error for InputError {}
+
+impl Inputs {
+    /// Create the inputs, in the order of the configuration file.
+    pub fn new(inputs: Vec<(String, Output)>) -> Self {
+        Inputs(inputs)
+    }
+
+    /// Iter over the input names, in the order of the configuration file.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(n, _)| n.as_str())
+    }
+
+    fn get(&self, name: &str) -> Result<&Output, InputError> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, m)| m)
+            .ok_or_else(|| InputError::Unknown(name.to_string()))
+    }
+
+    /// List the files of the input, sorted. A file input has the only path `""`.
+    pub fn list(&self, name: &str) -> Result<Vec<&str>, InputError> {
+        Ok(match self.get(name)? {
+            Output::File(_) => vec![""],
+            Output::Directory(d) => d.keys().map(String::as_str).collect(),
+        })
+    }
+
+    /// Open the file `path` of the input, the empty path for a file input.
+    pub fn open(&self, name: &str, path: &str) -> Result<&[u8], InputError> {
+        match self.get(name)? {
+            Output::File(content) if path.is_empty() => Some(content.as_slice()),
+            Output::Directory(d) => d.get(path).map(Vec::as_slice),
+            Output::File(_) => None,
+        }
+        .ok_or_else(|| InputError::NotFound(name.to_string(), path.to_string()))
+    }
+}
+
+#[test]
+fn test_inputs() {
+    let mut src = crate::configuration::Directory::new();
+    src.insert("main.c".to_string(), b"int main;".to_vec());
+    src.insert("lib/a.c".to_string(), b"int a;".to_vec());
+    let inputs = Inputs::new(vec![
+        ("src".to_string(), Output::Directory(src)),
+        ("script".to_string(), Output::File(b"SECTIONS {}".to_vec())),
+    ]);
+
+    assert_eq!(vec!["src", "script"], inputs.names().collect::<Vec<_>>());
+    assert_eq!(Ok(vec!["lib/a.c", "main.c"]), inputs.list("src"));
+    assert_eq!(Ok(vec![""]), inputs.list("script"));
+    assert_eq!(Ok(&b"int a;"[..]), inputs.open("src", "lib/a.c"));
+    assert_eq!(Ok(&b"SECTIONS {}"[..]), inputs.open("script", ""));
+    assert_eq!(
+        Err(InputError::NotFound("src".to_string(), "b.c".to_string())),
+        inputs.open("src", "b.c")
+    );
+    assert_eq!(
+        Err(InputError::NotFound("script".to_string(), "a".to_string())),
+        inputs.open("script", "a")
+    );
+    assert_eq!(
+        Err(InputError::Unknown("headers".to_string())),
+        inputs.list("headers")
+    );
+}
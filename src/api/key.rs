@@ -0,0 +1,238 @@
+use super::{hex, Query, Record, Secrets};
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::{error::Error, fmt, fs, io};
+
+/// The local keystore with the public keys, relative to the repository.
+pub const KEYSTORE_DIR: &str = ".cage/keys";
+
+/// The secret with the project seed, declared with `secret keySeed` and given
+/// like the other secrets, by `CAGE_SECRET_KEY_SEED` or the secret file.
+pub const SEED_SECRET: &str = "keySeed";
+
+/// The minimal length of the seed in bytes, a shorter seed can be guessed.
+pub const MIN_SEED_LEN: usize = 32;
+
+/// The domain of the key derivation, change it changes all keys.
+const DOMAIN: &[u8] = b"cage-key-0";
+
+/// Derive the ed25519 keys from the project seed and a label. The same seed
+/// and the same label give the same keys on any machine.
+pub struct Keystore {
+    dir: PathBuf,
+    seed: Vec<u8>,
+}
+
+/// An ed25519 key pair, the private key is not printed.
+pub struct KeyPair(SigningKey);
+
+#[derive(Debug)]
+pub enum KeyError {
+    Io(io::Error),
+    /// The label is empty or contains a char other than an ASCII letter, a
+    /// digit, `-`, `_` or `.`.
+    InvalidLabel(String),
+    /// The public key in the keystore is not the derived one, the seed changed.
+    Mismatch(String),
+    /// The secret [`SEED_SECRET`] is not declared or not given.
+    MissingSeed,
+    /// The seed is shorter than [`MIN_SEED_LEN`], with its length.
+    ShortSeed(usize),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::InvalidLabel(l) => write!(f, "Invalid key label {:?}", l),
+            Self::Mismatch(l) => write!(
+                f,
+                "The key {:?} is not the key of the keystore, the seed changed",
+                l
+            ),
+            Self::MissingSeed => write!(
+                f,
+                "The keys need the secret {:?}, declare it and give its value",
+                SEED_SECRET
+            ),
+            Self::ShortSeed(len) => write!(
+                f,
+                "The seed has {} bytes, it needs at least {} bytes",
+                len, MIN_SEED_LEN
+            ),
+        }
+    }
+}
+
+impl Error for KeyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KeyError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keystore").field("dir", &self.dir).finish()
+    }
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeyPair({})", hex(&self.public()))
+    }
+}
+
+impl KeyPair {
+    /// The public key.
+    pub fn public(&self) -> [u8; 32] {
+        self.0.verifying_key().to_bytes()
+    }
+
+    /// Sign the message, ed25519 signatures are deterministic.
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.0.sign(message).to_bytes()
+    }
+}
+
+impl Keystore {
+    /// Create the keystore in the directory `dir` with the seed, it must have
+    /// at least [`MIN_SEED_LEN`] bytes.
+    pub fn new(dir: &Path, seed: &[u8]) -> Result<Self, KeyError> {
+        if seed.len() < MIN_SEED_LEN {
+            return Err(KeyError::ShortSeed(seed.len()));
+        }
+        Ok(Keystore {
+            dir: dir.to_path_buf(),
+            seed: seed.to_vec(),
+        })
+    }
+
+    /// Create the keystore in the directory `dir`, usually [`KEYSTORE_DIR`],
+    /// with the seed from the secret [`SEED_SECRET`].
+    pub fn from_secrets(dir: &Path, secrets: &Secrets) -> Result<Self, KeyError> {
+        match secrets.value(SEED_SECRET) {
+            Some(seed) => Keystore::new(dir, seed.as_bytes()),
+            None => Err(KeyError::MissingSeed),
+        }
+    }
+
+    /// Answer a generator that asks the key pair `label`, and record the query
+    /// with the public key. The first time, the public key is saved into the
+    /// keystore as `<label>.pub`; after, the derived key must be the same.
+    pub fn keypair(&self, record: &mut Record, label: &str) -> Result<KeyPair, KeyError> {
+        if label.is_empty()
+            || label.starts_with('.')
+            || !label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(KeyError::InvalidLabel(label.to_string()));
+        }
+
+        let pair = KeyPair(SigningKey::from_bytes(&self.derive(label)));
+        let public = hex(&pair.public());
+        let file = self.dir.join(format!("{}.pub", label));
+        match fs::read_to_string(&file) {
+            Ok(saved) if saved.trim() == public => {}
+            Ok(_) => return Err(KeyError::Mismatch(label.to_string())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir_all(&self.dir)?;
+                fs::write(&file, format!("{}\n", public))?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        record.push(Query::Key(label.to_string(), public));
+        Ok(pair)
+    }
+
+    /// The private key bytes: SHA-256 of the domain, the seed and the label,
+    /// each prefixed by its length.
+    fn derive(&self, label: &str) -> [u8; 32] {
+        let mut h = Sha256::new();
+        for part in [DOMAIN, &self.seed, label.as_bytes()].iter() {
+            h.update((part.len() as u64).to_le_bytes());
+            h.update(part);
+        }
+        h.finalize().into()
+    }
+}
+
+#[test]
+fn test_keystore() {
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    const SEED: &[u8] = b"the project seed of at least 32 bytes";
+    let dir = crate::temp::TempDir::new("key");
+
+    let store = Keystore::new(&dir, SEED).unwrap();
+    let mut record = Record::default();
+    let apk = store.keypair(&mut record, "apk-release").unwrap();
+    let other = store.keypair(&mut record, "other").unwrap();
+    assert_ne!(apk.public(), other.public());
+    assert_eq!(
+        format!("{}\n", hex(&apk.public())),
+        fs::read_to_string(dir.join("apk-release.pub")).unwrap()
+    );
+    assert_eq!(
+        vec![&Query::Key("apk-release".to_string(), hex(&apk.public()))],
+        record.queries().take(1).collect::<Vec<_>>()
+    );
+    assert!(record.is_shareable());
+
+    // An other machine, with the same seed.
+    let again = Keystore::new(&dir, SEED)
+        .unwrap()
+        .keypair(&mut Record::default(), "apk-release")
+        .unwrap();
+    assert_eq!(apk.public(), again.public());
+    assert_eq!(apk.sign(b"message"), again.sign(b"message"));
+    let key = VerifyingKey::from_bytes(&apk.public()).unwrap();
+    let signature = Signature::from_bytes(&apk.sign(b"message"));
+    assert!(key.verify(b"message", &signature).is_ok());
+
+    assert!(matches!(
+        Keystore::new(&dir, b"an other seed of at least 32 bytes")
+            .unwrap()
+            .keypair(&mut Record::default(), "apk-release"),
+        Err(KeyError::Mismatch(l)) if l == "apk-release"
+    ));
+    for label in ["", "../a", ".hidden", "a b"].iter() {
+        assert!(matches!(
+            store.keypair(&mut Record::default(), label),
+            Err(KeyError::InvalidLabel(_))
+        ));
+    }
+
+    assert!(matches!(
+        Keystore::new(&dir, b"short"),
+        Err(KeyError::ShortSeed(5))
+    ));
+    let secrets = |value: &str| {
+        let value = value.to_string();
+        Secrets::load(vec![SEED_SECRET], move |_| Some(value.clone()), None).unwrap()
+    };
+    let from_secret = Keystore::from_secrets(&dir, &secrets(std::str::from_utf8(SEED).unwrap()))
+        .unwrap()
+        .keypair(&mut Record::default(), "apk-release")
+        .unwrap();
+    assert_eq!(apk.public(), from_secret.public());
+    assert!(matches!(
+        Keystore::from_secrets(&dir, &secrets("")),
+        Err(KeyError::ShortSeed(0))
+    ));
+    assert!(matches!(
+        Keystore::from_secrets(&dir, &Secrets::load(vec![], |_| None, None).unwrap()),
+        Err(KeyError::MissingSeed)
+    ));
+}
@@ -0,0 +1,24 @@
+//! The API given by the orchestrator to the generators.
+
+mod documentation;
+mod input;
+mod key;
+mod option;
+mod record;
+mod secret;
+mod tag;
+mod version;
+
+pub use documentation::{Documentation, DocumentationError, Entry};
+pub use input::{InputError, Inputs};
+pub use key::{KeyError, KeyPair, Keystore, KEYSTORE_DIR, MIN_SEED_LEN, SEED_SECRET};
+pub use option::Options;
+pub use record::{Query, Record};
+pub use secret::{env_name, SecretError, Secrets, SECRET_FILE};
+pub use tag::{TagError, Tags};
+pub use version::version;
+
+/// Get the lowercase hexadecimal string of the bytes.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
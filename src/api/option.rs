@@ -0,0 +1,50 @@
+use super::{Query, Record};
+
+/// The options of one generator call, given after the generator in the
+/// configuration: `| $"https://exemple.com/min" {"level": $"3"}`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Options(Vec<(String, String)>);
+
+impl Options {
+    /// Create the options from the names and the values, in the order of the file.
+    pub fn new(options: Vec<(String, String)>) -> Self {
+        Options(options)
+    }
+
+    /// Answer a generator that asks an option, and record the query. None if
+    /// the option is not given. All the given options are in the cache key of
+    /// the call, also the ones not asked.
+    pub fn get(&self, record: &mut Record, name: &str) -> Option<&str> {
+        let value = self
+            .0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str());
+        record.push(Query::Option(name.to_string(), value.map(String::from)));
+        value
+    }
+}
+
+#[test]
+fn test_options() {
+    let options = Options::new(vec![
+        ("level".to_string(), "3".to_string()),
+        ("entry".to_string(), "main".to_string()),
+    ]);
+    let mut record = Record::default();
+    assert_eq!(Some("3"), options.get(&mut record, "level"));
+    assert_eq!(None, options.get(&mut record, "debug"));
+    assert_eq!(
+        vec![
+            &Query::Option("level".to_string(), Some("3".to_string())),
+            &Query::Option("debug".to_string(), None),
+        ],
+        record.queries().collect::<Vec<_>>()
+    );
+
+    let other = Options::new(vec![("level".to_string(), "2".to_string())]);
+    let mut other_record = Record::default();
+    other.get(&mut other_record, "level");
+    other.get(&mut other_record, "debug");
+    assert_ne!(record.key(), other_record.key());
+}
@@ -0,0 +1,97 @@
+use crate::lock::Dependency;
+
+/// A question asked by a generator to the orchestrator, with the answer.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Query {
+    /// The version of a generator from its URL, `None` if it's not in the lock file.
+    Version(String, Option<Dependency>),
+    /// Is the tag set?
+    Tag(String, bool),
+    /// A secret, with the hash of its value.
+    Secret(String, String),
+    /// A derived key pair, with the public key in hexadecimal.
+    Key(String, String),
+    /// An option of the generator call, `None` if it's not given.
+    Option(String, Option<String>),
+}
+
+/// All queries of one generator call. Asking an information is recorded, so the
+/// answers are a part of the cache key of the generator outputs.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Record(Vec<Query>);
+
+impl Record {
+    /// Add a query to the record.
+    pub fn push(&mut self, q: Query) {
+        self.0.push(q);
+    }
+
+    /// Iter over the queries, in the order of the generator call.
+    pub fn queries(&self) -> impl Iterator<Item = &Query> {
+        self.0.iter()
+    }
+
+    /// The outputs can be in a shared cache, no secret is asked.
+    pub fn is_shareable(&self) -> bool {
+        !self.0.iter().any(|q| matches!(q, Query::Secret(..)))
+    }
+
+    /// The bytes to add to the cache key. The same queries with the same answers
+    /// get the same key, in any order.
+    pub fn key(&self) -> Vec<u8> {
+        let mut queries: Vec<Vec<u8>> = self.0.iter().map(Query::key).collect();
+        queries.sort();
+        queries.dedup();
+        bincode::serialize(&queries).unwrap()
+    }
+}
+
+impl Query {
+    fn key(&self) -> Vec<u8> {
+        match self {
+            Query::Version(url, None) => bincode::serialize(&("version", url, None::<()>)),
+            Query::Version(url, Some(Dependency { version, hash })) => {
+                bincode::serialize(&("version", url, Some((version, hash))))
+            }
+            Query::Tag(name, set) => bincode::serialize(&("tag", name, set)),
+            Query::Secret(name, hash) => bincode::serialize(&("secret", name, hash)),
+            Query::Key(label, public) => bincode::serialize(&("key", label, public)),
+            Query::Option(name, value) => bincode::serialize(&("option", name, value)),
+        }
+        .unwrap()
+    }
+}
+
+#[test]
+fn test_record() {
+    let dep = Dependency {
+        version: "1.0.0".to_string(),
+        hash: "sha256:5e2b".to_string(),
+    };
+    let mut a = Record::default();
+    a.push(Query::Version("https://exemple.com/a".to_string(), None));
+    a.push(Query::Version(
+        "https://exemple.com/b".to_string(),
+        Some(dep.clone()),
+    ));
+
+    let mut b = Record::default();
+    b.push(Query::Version(
+        "https://exemple.com/b".to_string(),
+        Some(dep.clone()),
+    ));
+    b.push(Query::Version("https://exemple.com/a".to_string(), None));
+    b.push(Query::Version("https://exemple.com/a".to_string(), None));
+    assert_eq!(a.key(), b.key());
+
+    let mut c = Record::default();
+    c.push(Query::Version("https://exemple.com/a".to_string(), None));
+    c.push(Query::Version(
+        "https://exemple.com/b".to_string(),
+        Some(Dependency {
+            version: "1.0.1".to_string(),
+            ..dep
+        }),
+    ));
+    assert_ne!(a.key(), c.key());
+}
@@ -0,0 +1,196 @@
+use super::{hex, Query, Record};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::{error::Error, fmt};
+
+/// The prefix of the environment variables with a secret, like `CAGE_SECRET_SIGN_KEY`
+/// for the secret `signKey`.
+pub const ENV_PREFIX: &str = "CAGE_SECRET_";
+
+/// The local file with the secrets, relative to the repository. One secret by
+/// line: the name, a space and the value. Never commit it.
+pub const SECRET_FILE: &str = ".cage/secrets";
+
+/// The secrets declared in the configuration, with the values given by the user.
+#[derive(Clone)]
+pub struct Secrets {
+    declared: HashSet<String>,
+    values: HashMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SecretError {
+    /// A line of the secret file without a name and a value.
+    InvalidLine(usize),
+    /// A generator ask a secret not given by the user.
+    Missing(String),
+    /// A generator ask for a secret not declared.
+    UnknownQueried(String),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLine(l) => write!(
+                f,
+                "Invalid secret file at line {}, expected a name and a value",
+                l
+            ),
+            Self::Missing(s) => write!(
+                f,
+                "The secret {:?} is not given, set the variable {} or add it into {}",
+                s,
+                env_name(s),
+                SECRET_FILE
+            ),
+            Self::UnknownQueried(s) => write!(f, "A generator ask the unknown secret {:?}", s),
+        }
+    }
+}
+impl Error for SecretError {}
+
+/// The values are not printed.
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secrets")
+            .field("declared", &self.declared)
+            .field("given", &self.values.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Get the environment variable of the secret: the name in upper snake case,
+/// with the prefix [`ENV_PREFIX`]. The parser rejects two secrets with the
+/// same variable.
+pub fn env_name(secret: &str) -> String {
+    let mut name = String::from(ENV_PREFIX);
+    let mut previous_lower = false;
+    for c in secret.chars() {
+        if c.is_uppercase() && previous_lower {
+            name.push('_');
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        if c.is_alphanumeric() {
+            name.extend(c.to_uppercase());
+        } else {
+            name.push('_');
+        }
+    }
+    name
+}
+
+impl Secrets {
+    /// Load the declared secrets. A value from the environment (read with `env`)
+    /// has priority on a value from the content of the secret `file`, an empty
+    /// variable is a given empty secret. A secret not given is an error only
+    /// when a generator asks for it.
+    pub fn load<'a, D, E>(declared: D, env: E, file: Option<&str>) -> Result<Self, SecretError>
+    where
+        D: IntoIterator<Item = &'a str>,
+        E: Fn(&str) -> Option<String>,
+    {
+        let declared: HashSet<String> = declared.into_iter().map(String::from).collect();
+        let mut from_file = HashMap::new();
+        for (i, line) in file.unwrap_or("").lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.split_once(char::is_whitespace) {
+                Some((name, value)) if !value.trim().is_empty() => {
+                    from_file.insert(name, value.trim());
+                }
+                _ => return Err(SecretError::InvalidLine(i + 1)),
+            }
+        }
+
+        let mut values = HashMap::new();
+        for name in declared.iter() {
+            let value =
+                env(&env_name(name)).or_else(|| from_file.get(&name[..]).map(|v| v.to_string()));
+            if let Some(v) = value {
+                values.insert(name.clone(), v);
+            }
+        }
+        Ok(Secrets { declared, values })
+    }
+
+    /// Answer a generator that asks a secret, and record the query with a hash
+    /// of the value. The outputs are not shareable, see [`Record::is_shareable`].
+    pub fn get(&self, record: &mut Record, name: &str) -> Result<&str, SecretError> {
+        if !self.declared.contains(name) {
+            return Err(SecretError::UnknownQueried(name.to_string()));
+        }
+        let value = self
+            .values
+            .get(name)
+            .ok_or_else(|| SecretError::Missing(name.to_string()))?;
+        record.push(Query::Secret(name.to_string(), fingerprint(value)));
+        Ok(value)
+    }
+
+    /// Get the value of a secret without recording it, for the orchestrator.
+    pub(super) fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Replace the secret values in a text, for the logs and the traces. An
+    /// empty secret is not replaced.
+    pub fn redact(&self, text: &str) -> String {
+        let mut values: Vec<&String> = self.values.values().filter(|v| !v.is_empty()).collect();
+        // The longest first, if a secret contains another one.
+        values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        let mut text = text.to_string();
+        for v in values {
+            text = text.replace(v.as_str(), "***");
+        }
+        text
+    }
+}
+
+/// The hash of the value, to know when a secret changes without keeping it.
+fn fingerprint(value: &str) -> String {
+    format!("sha256:{}", hex(&Sha256::digest(value.as_bytes())))
+}
+
+#[test]
+fn test_secrets() {
+    assert_eq!("CAGE_SECRET_SIGN_KEY", env_name("signKey"));
+    assert_eq!("CAGE_SECRET_APK2_KEY", env_name("apk2-key"));
+
+    let env = |name: &str| match name {
+        "CAGE_SECRET_SIGN_KEY" => Some("from env".to_string()),
+        _ => None,
+    };
+    assert_eq!(
+        SecretError::InvalidLine(2),
+        Secrets::load(vec!["signKey"], env, Some("a b\nalone\n")).unwrap_err()
+    );
+
+    let file = "signKey from file\n\ntoken  abc def \n";
+    let secrets = Secrets::load(vec!["signKey", "token", "other"], env, Some(file)).unwrap();
+    let mut record = Record::default();
+    assert_eq!(Ok("from env"), secrets.get(&mut record, "signKey"));
+    assert_eq!(Ok("abc def"), secrets.get(&mut record, "token"));
+    assert_eq!(
+        Err(SecretError::Missing("other".to_string())),
+        secrets.get(&mut record, "other")
+    );
+    assert_eq!(
+        Err(SecretError::UnknownQueried("unknown".to_string())),
+        secrets.get(&mut record, "unknown")
+    );
+
+    assert_eq!(2, record.queries().count());
+    assert!(!record.is_shareable());
+    assert!(!format!("{:?}", record).contains("from env"));
+    assert!(!format!("{:?}", secrets).contains("from env"));
+    assert_eq!(
+        "key=*** token=***",
+        secrets.redact("key=from env token=abc def")
+    );
+
+    let empty = Secrets::load(vec!["token"], |_| Some(String::new()), Some(file)).unwrap();
+    assert_eq!(Ok(""), empty.get(&mut record, "token"));
+    assert_eq!("abc", empty.redact("abc"));
+}
@@ -0,0 +1,93 @@
+use super::{Query, Record};
+use std::collections::HashSet;
+use std::{error::Error, fmt};
+
+/// The tags declared in the configuration, and the activated ones.
+#[derive(Debug)]
+pub struct Tags {
+    declared: HashSet<String>,
+    active: HashSet<String>,
+}
+
+/// An error about a tag not declared in the configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TagError {
+    /// The user activate a tag not declared.
+    UnknownActivated(String),
+    /// A generator ask for a tag not declared.
+    UnknownQueried(String),
+}
+
+impl fmt::Display for TagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownActivated(t) => write!(f, "Can not activate the unknown tag {:?}", t),
+            Self::UnknownQueried(t) => write!(f, "A generator ask the unknown tag {:?}", t),
+        }
+    }
+}
+impl Error for TagError {}
+
+impl Tags {
+    /// Create the tags from the declared tags of the configuration and the tags
+    /// activated by the user, like with `--tag release`.
+    pub fn new<'a, D, A>(declared: D, active: A) -> Result<Self, TagError>
+    where
+        D: IntoIterator<Item = &'a str>,
+        A: IntoIterator<Item = &'a str>,
+    {
+        let declared: HashSet<String> = declared.into_iter().map(String::from).collect();
+        let mut tags = Tags {
+            declared,
+            active: HashSet::new(),
+        };
+        for t in active {
+            if !tags.declared.contains(t) {
+                return Err(TagError::UnknownActivated(t.to_string()));
+            }
+            tags.active.insert(t.to_string());
+        }
+        Ok(tags)
+    }
+
+    /// Iter over the tags activated by the user, in any order.
+    pub fn active(&self) -> impl Iterator<Item = &str> {
+        self.active.iter().map(String::as_str)
+    }
+
+    /// Answer a generator that asks if the tag is set, and record the query.
+    pub fn is_set(&self, record: &mut Record, tag: &str) -> Result<bool, TagError> {
+        if !self.declared.contains(tag) {
+            return Err(TagError::UnknownQueried(tag.to_string()));
+        }
+        let set = self.active.contains(tag);
+        record.push(Query::Tag(tag.to_string(), set));
+        Ok(set)
+    }
+}
+
+#[test]
+fn test_tags() {
+    assert_eq!(
+        TagError::UnknownActivated("debug".to_string()),
+        Tags::new(vec!["release"], vec!["debug"]).unwrap_err()
+    );
+
+    let tags = Tags::new(vec!["release", "wasm"], vec!["release"]).unwrap();
+    let mut record = Record::default();
+    assert_eq!(Ok(true), tags.is_set(&mut record, "release"));
+    assert_eq!(
+        Err(TagError::UnknownQueried("debug".to_string())),
+        tags.is_set(&mut record, "debug")
+    );
+    assert_eq!(
+        vec![&Query::Tag("release".to_string(), true)],
+        record.queries().collect::<Vec<_>>()
+    );
+
+    // The wasm tag is not consulted, so activate it does not change the cache key.
+    let other = Tags::new(vec!["release", "wasm"], vec!["release", "wasm"]).unwrap();
+    let mut other_record = Record::default();
+    assert_eq!(Ok(true), other.is_set(&mut other_record, "release"));
+    assert_eq!(record.key(), other_record.key());
+}
@@ -0,0 +1,43 @@
+use super::{Query, Record};
+use crate::lock::{Dependency, Lock};
+
+/// Answer a generator that asks the version of the generator `url`. The answer come
+/// from the lock file and the query is recorded.
+pub fn version<'a>(lock: &'a Lock, record: &mut Record, url: &str) -> Option<&'a Dependency> {
+    let d = lock.get(url);
+    record.push(Query::Version(url.to_string(), d.cloned()));
+    d
+}
+
+#[test]
+fn test_version() {
+    let mut lock = Lock::default();
+    lock.insert("https://exemple.com/minifier", "1.4.2", "sha256:5e2b");
+    let mut record = Record::default();
+
+    assert_eq!(
+        Some("1.4.2"),
+        version(&lock, &mut record, "https://exemple.com/minifier").map(|d| d.version.as_str())
+    );
+    assert_eq!(
+        None,
+        version(&lock, &mut record, "https://exemple.com/unknown")
+    );
+
+    let mut queries = record.queries();
+    assert_eq!(
+        Some(&Query::Version(
+            "https://exemple.com/minifier".to_string(),
+            lock.get("https://exemple.com/minifier").cloned()
+        )),
+        queries.next()
+    );
+    assert_eq!(
+        Some(&Query::Version(
+            "https://exemple.com/unknown".to_string(),
+            None
+        )),
+        queries.next()
+    );
+    assert_eq!(None, queries.next());
+}
@@ -0,0 +1,319 @@
+//! The archive of a project: the configuration, the lock file, the repository
+//! files read by the configuration and the WASM modules of the generators. It
+//! contains all to rebuild the project in the future, without network.
+
+use crate::configuration::{ConfigurationError, Tree};
+use crate::lock::{Lock, LockError};
+use bincode::Options;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path};
+use std::{error::Error, fmt, fs, io};
+
+/// The header of the archive file.
+const HEADER: &str = "CAGE-ARCHIVE-0";
+
+/// The directory of the downloaded generator modules, relative to the repository.
+pub const GENERATOR_DIR: &str = ".cage/generators";
+
+/// The content of an archive, files are by path relative to the repository.
+#[derive(Debug, Default, PartialEq)]
+pub struct Archive {
+    /// The path and the content of the configuration file.
+    pub config: (String, String),
+    /// The path and the content of the lock file, if it exists.
+    pub lock: Option<(String, String)>,
+    /// The repository files read by the build, with the path generators. A
+    /// link is stored as the file it links to.
+    pub sources: BTreeMap<String, Vec<u8>>,
+    /// The WASM modules of the URL generators, by URL.
+    pub generators: BTreeMap<String, Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    Configuration(ConfigurationError),
+    Lock(LockError),
+    /// The generator is not in the lock file, or its module is not downloaded.
+    MissingGenerator(String),
+    /// The file is not an archive.
+    Invalid,
+    /// A path in the archive is not relative to the repository.
+    InvalidPath(String),
+}
+
+impl Archive {
+    /// Create the archive of the configuration `config` and the lock file `lock`,
+    /// paths relative to the repository `root`.
+    pub fn create(root: &Path, config: &str, lock: &str) -> Result<Archive, ArchiveError> {
+        let content = fs::read_to_string(root.join(config))?;
+        let tree = Tree::parse(&content).map_err(ArchiveError::Configuration)?;
+        let lock_content = match fs::read_to_string(root.join(lock)) {
+            Ok(l) => Some(l),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let parsed = match &lock_content {
+            Some(l) => Lock::parse(l).map_err(ArchiveError::Lock)?,
+            None => Lock::default(),
+        };
+
+        let mut archive = Archive {
+            config: (config.to_string(), content.clone()),
+            lock: lock_content.map(|l| (lock.to_string(), l)),
+            ..Archive::default()
+        };
+
+        for path in tree.read_files(root)? {
+            let content = fs::read(root.join(&path))?;
+            archive.sources.insert(path, content);
+        }
+
+        for url in tree.generator_url_list(&HashMap::new()) {
+            let d = parsed
+                .get(url)
+                .ok_or_else(|| ArchiveError::MissingGenerator(url.to_string()))?;
+            let module = match fs::read(root.join(generator_path(&d.hash))) {
+                Ok(m) => m,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Err(ArchiveError::MissingGenerator(url.to_string()))
+                }
+                Err(e) => return Err(e.into()),
+            };
+            archive.generators.insert(url.to_string(), module);
+        }
+
+        Ok(archive)
+    }
+
+    /// Write all files of the archive into the repository `root`, the generator
+    /// modules are written into [`GENERATOR_DIR`].
+    pub fn extract(&self, root: &Path) -> Result<(), ArchiveError> {
+        let lock = match &self.lock {
+            Some((_, l)) => Lock::parse(l).map_err(ArchiveError::Lock)?,
+            None => Lock::default(),
+        };
+
+        let mut files: Vec<(String, &[u8])> =
+            vec![(self.config.0.clone(), self.config.1.as_bytes())];
+        if let Some((path, content)) = &self.lock {
+            files.push((path.clone(), content.as_bytes()));
+        }
+        for (path, content) in self.sources.iter() {
+            files.push((path.clone(), content));
+        }
+        for (url, module) in self.generators.iter() {
+            let d = lock
+                .get(url)
+                .ok_or_else(|| ArchiveError::MissingGenerator(url.clone()))?;
+            files.push((generator_path(&d.hash), module));
+        }
+
+        // Check all paths before writing any file.
+        for (path, _) in files.iter() {
+            if Path::new(path)
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_)))
+            {
+                return Err(ArchiveError::InvalidPath(path.clone()));
+            }
+        }
+        for (path, content) in files {
+            let file = root.join(&path);
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(file, content)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the archive.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let sources: Vec<(&String, &Vec<u8>)> = self.sources.iter().collect();
+        let generators: Vec<(&String, &Vec<u8>)> = self.generators.iter().collect();
+        options()
+            .serialize(&(HEADER, &self.config, &self.lock, sources, generators))
+            .unwrap()
+    }
+
+    /// Deserialize the archive.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Archive, ArchiveError> {
+        type Content = (
+            String,
+            (String, String),
+            Option<(String, String)>,
+            Vec<(String, Vec<u8>)>,
+            Vec<(String, Vec<u8>)>,
+        );
+        // The limit avoids huge allocations from an invalid length.
+        match options()
+            .with_limit(bytes.len() as u64)
+            .deserialize::<Content>(bytes)
+        {
+            Ok((header, config, lock, sources, generators)) if header == HEADER => Ok(Archive {
+                config,
+                lock,
+                sources: sources.into_iter().collect(),
+                generators: generators.into_iter().collect(),
+            }),
+            _ => Err(ArchiveError::Invalid),
+        }
+    }
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().with_fixint_encoding()
+}
+
+/// Get the path of the downloaded module from its hash, relative to the repository.
+pub fn generator_path(hash: &str) -> String {
+    format!("{}/{}.wasm", GENERATOR_DIR, hash.replace(':', "-"))
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Configuration(e) => write!(f, "{}", e),
+            Self::Lock(e) => write!(f, "{}", e),
+            Self::MissingGenerator(url) => {
+                write!(f, "The module of the generator {:?} is not available", url)
+            }
+            Self::Invalid => write!(f, "The file is not a valid archive"),
+            Self::InvalidPath(p) => write!(f, "Invalid path in the archive: {:?}", p),
+        }
+    }
+}
+
+impl Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Configuration(e) => Some(e),
+            Self::Lock(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_archive() {
+    let dir = crate::temp::TempDir::new("archive");
+    let write = |path: &str, content: &[u8]| {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    };
+    write(
+        "build.cage",
+        b"CAGE-BUILD-0
+dir front \"front/\" > $\"https://exemple.com/min\"
+dir $pkg [front, \"README\" > \"tools/gen.wasm\"]
+",
+    );
+    write(
+        "cage.lock",
+        b"CAGE-LOCK-0\nhttps://exemple.com/min 1.0.0 sha256:5e2b\n",
+    );
+    write("front/index.html", b"<html>");
+    write("front/js/app.js", b"app");
+    write("tools/gen.wasm", b"\0asm");
+    write("other.txt", b"not read");
+
+    assert!(matches!(
+        Archive::create(&dir, "build.cage", "cage.lock"),
+        Err(ArchiveError::MissingGenerator(url)) if url == "https://exemple.com/min"
+    ));
+    write(".cage/generators/sha256-5e2b.wasm", b"\0asm min");
+
+    let archive = Archive::create(&dir, "build.cage", "cage.lock").unwrap();
+    assert_eq!(
+        vec!["front/index.html", "front/js/app.js", "tools/gen.wasm"],
+        archive.sources.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(&b"\0asm min".to_vec()),
+        archive.generators.get("https://exemple.com/min")
+    );
+
+    let bytes = archive.to_bytes();
+    assert_eq!(archive, Archive::from_bytes(&bytes).unwrap());
+    assert!(matches!(
+        Archive::from_bytes(b"no archive"),
+        Err(ArchiveError::Invalid)
+    ));
+
+    let out = dir.join("out");
+    archive.extract(&out).unwrap();
+    assert_eq!(
+        fs::read(dir.join("build.cage")).unwrap(),
+        fs::read(out.join("build.cage")).unwrap()
+    );
+    assert_eq!(
+        b"app".to_vec(),
+        fs::read(out.join("front/js/app.js")).unwrap()
+    );
+    assert_eq!(
+        b"\0asm min".to_vec(),
+        fs::read(out.join(".cage/generators/sha256-5e2b.wasm")).unwrap()
+    );
+    assert!(!out.join("other.txt").exists());
+
+    let mut evil = Archive::from_bytes(&bytes).unwrap();
+    evil.sources.insert("../evil".to_string(), Vec::new());
+    assert!(matches!(
+        evil.extract(&out),
+        Err(ArchiveError::InvalidPath(p)) if p == "../evil"
+    ));
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("..", dir.join("front/js/loop")).unwrap();
+        std::os::unix::fs::symlink("app.js", dir.join("front/js/link.js")).unwrap();
+        let archive = Archive::create(&dir, "build.cage", "cage.lock").unwrap();
+        assert_eq!(
+            Some(&b"app".to_vec()),
+            archive.sources.get("front/js/link.js")
+        );
+        std::os::unix::fs::symlink("../../..", dir.join("front/js/up")).unwrap();
+        assert!(matches!(
+            Archive::create(&dir, "build.cage", "cage.lock"),
+            Err(ArchiveError::Io(_))
+        ));
+    }
+}
+
+#[test]
+fn test_archive_glob() {
+    let dir = crate::temp::TempDir::new("archive-glob");
+    for (path, content) in [
+        ("build.cage", "CAGE-BUILD-1\ndir $pkg \"**/*.md\"\n"),
+        (".cageignore", "draft.md\n"),
+        ("README.md", "readme"),
+        ("draft.md", "draft"),
+        ("doc/a.md", "a"),
+        ("doc/a.txt", "txt"),
+        (".git/HEAD.md", "git"),
+        (".cage/keys/a.md", "key"),
+        ("target/out.md", "out"),
+    ]
+    .iter()
+    {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    }
+
+    let archive = Archive::create(&dir, "build.cage", "cage.lock").unwrap();
+    assert_eq!(
+        vec![".cageignore", "README.md", "doc/a.md"],
+        archive.sources.keys().collect::<Vec<_>>()
+    );
+}
@@ -1,5 +1,364 @@
-// use cage;
+use cage::{
+    api::Secrets,
+    archive::Archive,
+    build::Build,
+    configuration::{self, cst::Cst, index},
+    lock::Lock,
+    lsp, serve,
+};
+use std::{env, error::Error, fs, io, path::Path, process, thread};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+const USAGE: &str = "Usage: cage <command> [arguments]
+       cage <file> [target]
+
+With a configuration file, build the target like `cage build`, with the same
+options. An executable configuration file can begin with the line
+`#!/usr/bin/env cage`.
+
+Commands:
+    archive <file> <output> Write the archive of the project to rebuild it without network
+    build [--tag <tag>]... [--out <path>] [--watch] <file> [target]
+                            Build the target, $pkg by default, into the path, out by
+                            default. With --watch, build again when a file read by the
+                            configuration changes. The generators can not be run yet
+    build [--tag <tag>]... [--out <path>] --from-archive <archive> [target]
+                            Build the target from the files of an archive
+    extract <archive> <dir> Write the files of an archive into the directory
+    fmt [--check] <file>... Format the configuration files, or check they are formatted
+    lsp                     Run the language server over stdio
+    migrate <file>...       Rewrite the configuration files to the newest version
+    refs <file> <name>      Print the definition and the uses of a variable
+    rename <file> <old> <new>
+                            Rename a variable in the configuration file
+    serve [--tag <tag>]... [--address <address>] <file> [target]
+                            Build the target and serve it over HTTP, on localhost:8080
+                            by default. Build again when a file read by the
+                            configuration changes
+    versions [lock file]    Print the resolved version of each generator";
+
+/// The default lock file.
+const LOCK_FILE: &str = "cage.lock";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let r = command(&args).unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+    if let Err(e) = r {
+        eprintln!("cage: {}", message(&*e));
+        process::exit(1);
+    }
+}
+
+/// Run the command of the arguments, None if they are not a command.
+fn command(args: &[&str]) -> Option<Result<(), Box<dyn Error>>> {
+    Some(match args[..] {
+        ["archive", config, output] => archive(config, output),
+        ["build", ref args @ ..] => build(args),
+        ["extract", archive, dir] => extract(archive, dir),
+        ["fmt", "--check", ref files @ ..] if !files.is_empty() => fmt(files, true),
+        ["fmt", ref files @ ..] if !files.is_empty() => fmt(files, false),
+        ["migrate", ref files @ ..] if !files.is_empty() => migrate(files),
+        ["lsp"] => lsp::run(io::stdin().lock(), io::stdout().lock()).map_err(|e| e.into()),
+        ["refs", file, name] => refs(file, name),
+        ["rename", file, old, new] => rename(file, old, new),
+        ["serve", ref args @ ..] => serve(args),
+        ["versions"] => versions(LOCK_FILE),
+        ["versions", lock] => versions(lock),
+        [file, ..] if Path::new(file).is_file() => build(args),
+        _ => return None,
+    })
+}
+
+/// The message of the error followed by the messages of its sources.
+fn message(e: &dyn Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(s) = source {
+        message.push_str(": ");
+        message.push_str(&s.to_string());
+        source = s.source();
+    }
+    message
+}
+
+/// The message of an error in the configuration file, with its sources.
+fn in_file(file: &str, e: &dyn Error) -> String {
+    format!("{}: {}", file, message(e))
+}
+
+/// Write the archive of the configuration, the lock file is in the same directory.
+fn archive(config: &str, output: &str) -> Result<(), Box<dyn Error>> {
+    let config = Path::new(config);
+    let root = config.parent().unwrap_or_else(|| Path::new(""));
+    let name = config
+        .file_name()
+        .ok_or_else(|| format!("invalid configuration file {:?}", config))?
+        .to_string_lossy();
+    let archive = Archive::create(root, &name, LOCK_FILE)?;
+    fs::write(output, archive.to_bytes())?;
     Ok(())
 }
+
+/// The options of `build` and `serve`.
+struct Options<'a> {
+    tags: Vec<&'a str>,
+    out: &'a str,
+    watch: bool,
+    from_archive: bool,
+    address: &'a str,
+    file: &'a str,
+    target: &'a str,
+}
+
+/// Read the options of the command, `--tag <tag>` can be repeated. `build` has
+/// `--out <path>`, `--watch` and `--from-archive`, `serve` has `--address <address>`.
+fn options<'a>(command: &str, args: &[&'a str]) -> Result<Options<'a>, Box<dyn Error>> {
+    let mut tags = Vec::new();
+    let mut out = "out";
+    let mut watch = false;
+    let mut from_archive = false;
+    let mut address = "localhost:8080";
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (command, *arg) {
+            (_, "--tag") => tags.push(*args.next().ok_or("--tag needs a tag")?),
+            ("build", "--out") => out = args.next().ok_or("--out needs a path")?,
+            ("build", "--watch") => watch = true,
+            ("build", "--from-archive") => from_archive = true,
+            ("serve", "--address") => address = args.next().ok_or("--address needs an address")?,
+            (_, o) if o.starts_with("--") => {
+                return Err(format!("{} has no option {}", command, o).into())
+            }
+            _ => positional.push(*arg),
+        }
+    }
+    let (file, target) = match positional[..] {
+        [file] => (file, "$pkg"),
+        [file, target] => (file, target),
+        _ => {
+            return Err(format!(
+                "{} needs a configuration file and at most a target",
+                command
+            )
+            .into())
+        }
+    };
+    Ok(Options {
+        tags,
+        out,
+        watch,
+        from_archive,
+        address,
+        file,
+        target,
+    })
+}
+
+/// Build the target, then with `--watch` build it again on each change.
+fn build(args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let o = options("build", args)?;
+    if o.from_archive {
+        return build_archive(&o);
+    }
+    let mut build = Build::new(Path::new(o.file), &o.tags).map_err(|e| in_file(o.file, &*e))?;
+    let secrets = build.secrets().clone();
+    let out = Path::new(o.out);
+    if o.watch {
+        build.watch(o.target, |output| {
+            report(
+                o.target,
+                &secrets,
+                output
+                    .map_err(|e| e.into())
+                    .and_then(|o| o.write(out).map_err(|e| e.into())),
+            );
+            true
+        })?;
+    } else {
+        let output = build
+            .run(o.target)
+            .map_err(|e| secrets.redact(&message(&e)))?;
+        output.write(out)?;
+    }
+    Ok(())
+}
+
+/// Extract the archive into a temporary directory and build the target from it.
+fn build_archive(o: &Options) -> Result<(), Box<dyn Error>> {
+    if o.watch {
+        return Err("--watch can not be used with --from-archive".into());
+    }
+    let archive = Archive::from_bytes(&fs::read(o.file)?).map_err(|e| in_file(o.file, &e))?;
+    let dir = env::temp_dir().join(format!("cage-archive-{}", process::id()));
+    let built = Build::from_archive(&archive, &dir, &o.tags).and_then(|mut build| {
+        let secrets = build.secrets().clone();
+        let output = build
+            .run(o.target)
+            .map_err(|e| secrets.redact(&message(&e)))?;
+        output.write(Path::new(o.out))?;
+        Ok(())
+    });
+    // The extracted files are removed, also when the build fails.
+    let _ = fs::remove_dir_all(&dir);
+    built
+}
+
+/// Build the target into a temporary directory and serve it, build it again
+/// on each change.
+fn serve(args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let o = options("serve", args)?;
+    let mut build = Build::new(Path::new(o.file), &o.tags).map_err(|e| in_file(o.file, &*e))?;
+    let secrets = build.secrets().clone();
+    let dir = env::temp_dir().join(format!("cage-serve-{}", process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let (root, address) = (dir.clone(), o.address.to_string());
+    thread::spawn(move || {
+        if let Err(e) = serve::serve(&root, &address[..]) {
+            eprintln!("cage: Can not serve on {}: {}", address, e);
+            process::exit(1);
+        }
+    });
+    eprintln!("cage: serving {} on http://{}", o.target, o.address);
+    build.watch(o.target, |output| {
+        // The files removed from the output must not be served.
+        let written = output
+            .map_err(|e| e.into())
+            .and_then(|output| serve::replace(&dir, |d| output.write(d)).map_err(|e| e.into()));
+        report(o.target, &secrets, written);
+        true
+    })?;
+    Ok(())
+}
+
+/// Print the result of a build when watching, a failed build does not stop the
+/// watch. The secret values are redacted.
+fn report(target: &str, secrets: &Secrets, result: Result<(), Box<dyn Error>>) {
+    match result {
+        Ok(()) => eprintln!("cage: built {}", target),
+        Err(e) => eprintln!("cage: {}", secrets.redact(&message(&*e))),
+    }
+}
+
+/// Write the files of the archive into the directory.
+fn extract(archive: &str, dir: &str) -> Result<(), Box<dyn Error>> {
+    Archive::from_bytes(&fs::read(archive)?)?.extract(Path::new(dir))?;
+    Ok(())
+}
+
+/// Format the configuration files in place, or with check, fail if one is not formatted.
+fn fmt(files: &[&str], check: bool) -> Result<(), Box<dyn Error>> {
+    let mut unformatted = Vec::new();
+    for file in files {
+        let config = fs::read_to_string(file)?;
+        let formatted = configuration::format(&config).map_err(|e| in_file(file, &e))?;
+        if formatted == config {
+            continue;
+        } else if check {
+            unformatted.push(*file);
+        } else {
+            fs::write(file, formatted)?;
+        }
+    }
+
+    if unformatted.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("not formatted: {}", unformatted.join(", ")).into())
+    }
+}
+
+/// Print the definition and the uses of the variable, one `file:line:column` by line.
+fn refs(file: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    let cst = Cst::parse(&fs::read_to_string(file)?).map_err(|e| in_file(file, &e))?;
+    let index = index::Index::new(&cst).map_err(|e| in_file(file, &e))?;
+    let symbol = index
+        .get(name)
+        .ok_or_else(|| format!("{}: The variable {:?} is not defined", file, name))?;
+    for t in std::iter::once(&symbol.definition).chain(symbol.uses.iter()) {
+        println!("{}:{}:{}", file, t.position.line, t.position.column);
+    }
+    Ok(())
+}
+
+/// Rename the variable in the configuration file, in place.
+fn rename(file: &str, old: &str, new: &str) -> Result<(), Box<dyn Error>> {
+    let config = fs::read_to_string(file)?;
+    let renamed = index::rename(&config, old, new).map_err(|e| in_file(file, &e))?;
+    fs::write(file, renamed)?;
+    Ok(())
+}
+
+/// Rewrite the configuration files to the newest version, in place.
+fn migrate(files: &[&str]) -> Result<(), Box<dyn Error>> {
+    for file in files {
+        let config = fs::read_to_string(file)?;
+        let migrated = configuration::migrate(&config).map_err(|e| in_file(file, &e))?;
+        if migrated != config {
+            fs::write(file, migrated)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the table of generators from the lock file.
+fn versions(lock: &str) -> Result<(), Box<dyn Error>> {
+    let lock = Lock::parse(&fs::read_to_string(lock)?)?;
+
+    let url_width = lock.iter().map(|(url, _)| url.len()).max().unwrap_or(0);
+    let version_width = lock.iter().map(|(_, d)| d.version.len()).max().unwrap_or(0);
+    let url_width = url_width.max("GENERATOR".len());
+    let version_width = version_width.max("VERSION".len());
+
+    println!(
+        "{:u$}  {:v$}  HASH",
+        "GENERATOR",
+        "VERSION",
+        u = url_width,
+        v = version_width
+    );
+    for (url, d) in lock.iter() {
+        println!(
+            "{:u$}  {:v$}  {}",
+            url,
+            d.version,
+            d.hash,
+            u = url_width,
+            v = version_width
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_command() {
+    let dir = env::temp_dir().join(format!("cage-test-command-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("build.cage");
+    fs::write(
+        &config,
+        "#!/usr/bin/env cage\nCAGE-BUILD-1\nfile $pkg $\"pkg\"\nfile doc $\"doc\"\n",
+    )
+    .unwrap();
+    let (config, out) = (config.to_str().unwrap(), dir.join("out"));
+    let out = out.to_str().unwrap();
+
+    let r = command(&[config, "--out", out]).unwrap();
+    let pkg = fs::read(out);
+    let r2 = command(&[config, "doc", "--out", out]).unwrap();
+    let doc = fs::read(out);
+    let none = command(&["no-file.cage"]).is_none();
+    let _ = fs::remove_dir_all(&dir);
+
+    r.unwrap();
+    r2.unwrap();
+    assert_eq!(b"pkg".to_vec(), pkg.unwrap());
+    assert_eq!(b"doc".to_vec(), doc.unwrap());
+    assert!(none);
+}
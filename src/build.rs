@@ -0,0 +1,154 @@
+//! Build the targets of a configuration file, for `cage build`.
+
+use crate::api::{Secrets, Tags, SECRET_FILE};
+use crate::archive::Archive;
+use crate::configuration::{BuildError, Cache, Output, Outputs, Tree};
+use crate::watch::{self, Snapshot};
+use std::path::{Path, PathBuf};
+use std::{env, error::Error, fs, io, time::Duration};
+
+/// The interval between two checks of the repository files, when watching.
+const WATCH_INTERVAL: Duration = Duration::from_millis(300);
+
+/// A configuration file and the outputs of its built definitions.
+#[derive(Debug)]
+pub struct Build {
+    /// The repository, the directory of the configuration file.
+    root: PathBuf,
+    tree: Tree,
+    /// The secrets declared in the configuration, with the values of the user.
+    secrets: Secrets,
+    /// The outputs of the generator calls, their keys have the tags set by the
+    /// user.
+    cache: Cache,
+    outputs: Outputs,
+}
+
+impl Build {
+    /// Read the configuration file, the configuration error keeps its cause as
+    /// source. The tags set by the user, like with
+    /// `--tag release`, must be declared in the configuration. The secrets are
+    /// read from the environment and the [`SECRET_FILE`] of the repository.
+    pub fn new(config: &Path, tags: &[&str]) -> Result<Build, Box<dyn Error>> {
+        let content = fs::read_to_string(config)?;
+        let tree = Tree::parse(&content)?;
+        let tags = Tags::new(tree.tags(), tags.iter().copied())?;
+        let root = config.parent().unwrap_or_else(|| Path::new(""));
+        let file = match fs::read_to_string(root.join(SECRET_FILE)) {
+            Ok(f) => Some(f),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let secrets = Secrets::load(tree.secrets(), |n| env::var(n).ok(), file.as_deref())?;
+        Ok(Build {
+            root: root.to_path_buf(),
+            tree,
+            secrets,
+            cache: Cache::with_tags(tags.active()),
+            outputs: Outputs::new(),
+        })
+    }
+
+    /// The secrets of the build, to redact the messages printed to the user.
+    pub fn secrets(&self) -> &Secrets {
+        &self.secrets
+    }
+
+    /// Extract the archive into the directory `dir` and read its configuration,
+    /// to build the project without its repository.
+    pub fn from_archive(
+        archive: &Archive,
+        dir: &Path,
+        tags: &[&str],
+    ) -> Result<Build, Box<dyn Error>> {
+        archive.extract(dir)?;
+        Build::new(&dir.join(&archive.config.0), tags)
+    }
+
+    /// Build the target and the definitions it needs, the definitions already
+    /// built are kept.
+    pub fn run(&mut self, target: &str) -> Result<&Output, BuildError> {
+        self.tree
+            .build(&self.root, target, &self.cache, &mut self.outputs)?;
+        Ok(&self.outputs[target])
+    }
+
+    /// Build the target, then build it again each time a repository file read
+    /// by the configuration changes. Only the invalidated definitions are built
+    /// again. `done` is called after each build, the watch stops when it
+    /// returns false.
+    pub fn watch<F>(&mut self, target: &str, mut done: F) -> io::Result<()>
+    where
+        F: FnMut(Result<&Output, BuildError>) -> bool,
+    {
+        let watched: Vec<String> = self
+            .tree
+            .watched_files()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let paths: Vec<&str> = watched.iter().map(String::as_str).collect();
+        let mut snapshot = Snapshot::take(&self.root, paths.iter().copied())?;
+        while done(self.run(target)) {
+            let (new, changes) = watch::wait(&self.root, &paths, &snapshot, WATCH_INTERVAL)?;
+            snapshot = new;
+            let changes: Vec<&str> = changes.iter().map(String::as_str).collect();
+            for name in self.tree.invalidated(&changes) {
+                self.outputs.remove(name);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_build() {
+    let dir = crate::temp::TempDir::new("cli-build");
+    let config = dir.join("build.cage");
+    fs::write(
+        &config,
+        "CAGE-BUILD-1\ntag release\nsecret cageTestToken\nfile $pkg $\"pkg\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join(".cage")).unwrap();
+    fs::write(dir.join(SECRET_FILE), "cageTestToken hunter2\n").unwrap();
+
+    let e = Build::new(&config, &["debug"]).unwrap_err();
+    assert_eq!("Can not activate the unknown tag \"debug\"", e.to_string());
+    let mut build = Build::new(&config, &["release"]).unwrap();
+    assert_eq!(&Output::File(b"pkg".to_vec()), build.run("$pkg").unwrap());
+    assert_eq!("key ***", build.secrets().redact("key hunter2"));
+
+    let archive = Archive::create(&dir, "build.cage", "cage.lock").unwrap();
+    let mut build = Build::from_archive(&archive, &dir.join("extracted"), &[]).unwrap();
+    assert_eq!(&Output::File(b"pkg".to_vec()), build.run("$pkg").unwrap());
+    assert!(dir.join("extracted/build.cage").is_file());
+}
+
+#[test]
+fn test_watch() {
+    let dir = crate::temp::TempDir::new("cli-watch");
+    fs::create_dir_all(dir.join("front")).unwrap();
+    fs::write(dir.join("front/app.js"), "app").unwrap();
+    let config = dir.join("build.cage");
+    fs::write(
+        &config,
+        "CAGE-BUILD-1
+dir $pkg \"front/\"\n",
+    )
+    .unwrap();
+
+    let mut build = Build::new(&config, &[]).unwrap();
+    let mut contents = Vec::new();
+    build
+        .watch("$pkg", |output| {
+            match output.unwrap() {
+                Output::Directory(d) => contents.push(d["app.js"].clone()),
+                Output::File(_) => panic!("Expected a directory"),
+            }
+            fs::write(dir.join("front/app.js"), "app modified").unwrap();
+            contents.len() < 2
+        })
+        .unwrap();
+    assert_eq!(vec![b"app".to_vec(), b"app modified".to_vec()], contents);
+}
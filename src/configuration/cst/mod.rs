@@ -0,0 +1,218 @@
+//! The lossless concrete syntax tree. Each byte of the configuration file is in
+//! one token, so the file can be rebuilt from the tree, used for tooling.
+
+mod parser;
+
+use super::{lexer::Word, version::Version, Position};
+use std::{fmt, ops::Range};
+
+/// The concrete syntax tree of a configuration file.
+#[derive(Debug, PartialEq)]
+pub struct Cst {
+    version: Version,
+    source: String,
+    root: Node,
+}
+
+/// A syntax node, with its tokens and sub nodes in the order of the file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub children: Vec<Element>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Element {
+    Node(Node),
+    Token(Token),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NodeKind {
+    /// The file: the header, the tags and the definitions.
+    Root,
+    /// `tag name`
+    Tag,
+    /// `secret name`
+    Secret,
+    /// `file name object` or `dir name object`
+    Definition,
+    /// An object, a pipe operator and a generator.
+    Pipe,
+    /// `object / "path"`, a sub path of a directory.
+    Select,
+    /// The generator after a pipe operator.
+    Generator,
+    /// `{"name": "value", ...}` after a generator, with [`NodeKind::Entry`].
+    Options,
+    /// `{"key": object, ...}`
+    Aggregation,
+    /// `"key": object` in an aggregation or in inputs, or `"name": "value"`
+    /// in options.
+    Entry,
+    /// `[object, ...]`
+    Composition,
+    /// `("name": object, ...)`, the named inputs of a generator, with
+    /// [`NodeKind::Entry`].
+    Inputs,
+    /// A file, a literal string or a variable used as an object.
+    Value,
+}
+
+/// One token, with its byte range in the source.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+    pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenKind {
+    /// The lines before the first token, the version header and its comments.
+    Header,
+    /// Spaces and tabulations.
+    Whitespace,
+    /// A lexer word, including the new lines and the comments.
+    Word(Word),
+}
+
+impl Cst {
+    /// The version of the grammar, from the header.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The source of the configuration file.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The root node, of kind [`NodeKind::Root`].
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// The text of the token.
+    pub fn text(&self, t: &Token) -> &str {
+        &self.source[t.span.clone()]
+    }
+}
+
+impl fmt::Display for Cst {
+    /// Rebuild the file from the tokens.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for t in self.root.tokens() {
+            f.write_str(self.text(t))?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Iter over all tokens of the node and its sub nodes, in order.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut v = Vec::new();
+        self.tokens_inter(&mut v);
+        v
+    }
+
+    fn tokens_inter<'a>(&'a self, v: &mut Vec<&'a Token>) {
+        for e in self.children.iter() {
+            match e {
+                Element::Node(n) => n.tokens_inter(v),
+                Element::Token(t) => v.push(t),
+            }
+        }
+    }
+
+    /// Iter over the direct sub nodes.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|e| match e {
+            Element::Node(n) => Some(n),
+            Element::Token(_) => None,
+        })
+    }
+
+    /// Iter over the direct words, without whitespaces, new lines and comments.
+    pub fn words(&self) -> impl Iterator<Item = (&Token, &Word)> {
+        self.children.iter().filter_map(|e| match e {
+            Element::Token(
+                t @ Token {
+                    kind: TokenKind::Word(w),
+                    ..
+                },
+            ) if !w.is_trivia() => Some((t, w)),
+            _ => None,
+        })
+    }
+}
+
+impl Word {
+    /// A new line or a comment, used only for the layout.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Word::NewLine | Word::Comment(_))
+    }
+}
+
+#[test]
+fn test_cst() {
+    let config = "# Before
+CAGE-BUILD-0 # header
+tag t   # comment
+file été \"front/\"\t> \"min\" ?? $\"https://exemple.com/min\"
+
+dir $pkg [
+	{ \"a.txt\": $\"A\" },
+	été,
+]  ";
+    let cst = Cst::parse(config).unwrap();
+    assert_eq!(config, cst.to_string());
+
+    let root = cst.root();
+    assert_eq!(NodeKind::Root, root.kind);
+    assert_eq!(
+        vec![NodeKind::Tag, NodeKind::Definition, NodeKind::Definition],
+        root.nodes().map(|n| n.kind).collect::<Vec<_>>()
+    );
+
+    let header = root.tokens()[0];
+    assert_eq!(TokenKind::Header, header.kind);
+    assert_eq!("# Before\nCAGE-BUILD-0 # header\n", cst.text(header));
+
+    let front = root.nodes().nth(1).unwrap();
+    let (key, _) = front.words().nth(1).unwrap();
+    assert_eq!(Position { line: 4, column: 6 }, key.position);
+    assert_eq!("été", cst.text(key));
+    let pipe = front.nodes().next().unwrap();
+    assert_eq!(NodeKind::Pipe, pipe.kind);
+    assert_eq!(
+        vec![NodeKind::Value, NodeKind::Generator],
+        pipe.nodes().map(|n| n.kind).collect::<Vec<_>>()
+    );
+
+    let tokens = root.tokens();
+    let ws: Vec<&str> = tokens
+        .iter()
+        .filter(|t| t.kind == TokenKind::Whitespace)
+        .map(|t| cst.text(t))
+        .collect();
+    assert_eq!(" ", ws[0]);
+    assert_eq!("   ", ws[1]);
+    assert_eq!("  ", *ws.last().unwrap());
+    let tab = Position {
+        line: 4,
+        column: 18,
+    };
+    assert!(tokens
+        .iter()
+        .any(|t| cst.text(t) == "\t" && t.position == tab));
+
+    assert_eq!(
+        Err(super::ConfigurationError::Parser(
+            Position { line: 2, column: 8 },
+            super::tree::ParserError::Unexpected(Word::Comma)
+        )),
+        Cst::parse("CAGE-BUILD-0\ndir a [,]\n")
+    );
+}
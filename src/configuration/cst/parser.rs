@@ -0,0 +1,398 @@
+use super::super::{
+    lexer::{Lexer, Word},
+    tree::ParserError,
+    version::Version,
+    ConfigurationError, Position,
+};
+use super::{Cst, Element, Node, NodeKind, Token, TokenKind};
+
+/// Create the concrete tree from the lexer words and the spaces between them.
+struct Parser<'a> {
+    source: &'a str,
+    lexer: Lexer<'a>,
+    /// The byte offset of the lexer input into the source.
+    base: usize,
+    /// The byte offset and the position of the end of the last token.
+    end: usize,
+    end_position: Position,
+    /// The whitespaces, new lines and comments before the next word.
+    trivia: Vec<Token>,
+    /// A word read but not consumed.
+    peeked: Option<Token>,
+    /// The position of the last word, used for the end of file error.
+    position: Position,
+}
+
+impl Cst {
+    /// Parse the configuration file, with its header.
+    pub fn parse(config: &str) -> Result<Cst, ConfigurationError> {
+        let (version, rest, line) = Version::get(config)?;
+
+        let base = config.len() - rest.len();
+        let start = Position { line, column: 1 };
+        let mut p = Parser {
+            source: config,
+            lexer: version.lexer(rest, line),
+            base,
+            end: base,
+            end_position: start,
+            trivia: Vec::new(),
+            peeked: None,
+            position: start,
+        };
+
+        let mut root = Node {
+            kind: NodeKind::Root,
+            children: vec![Element::Token(Token {
+                kind: TokenKind::Header,
+                span: 0..base,
+                position: Position { line: 1, column: 1 },
+            })],
+        };
+        while p.peek()?.is_some() {
+            root.children.push(Element::Node(p.statement()?));
+        }
+        p.flush(&mut root);
+
+        Ok(Cst {
+            version,
+            source: config.to_string(),
+            root,
+        })
+    }
+}
+
+fn err<T>(position: Position, e: ParserError) -> Result<T, ConfigurationError> {
+    Err(ConfigurationError::Parser(position, e))
+}
+
+/// Get the position after the text that begins at the position.
+fn advance(mut position: Position, text: &str) -> Position {
+    for c in text.chars() {
+        if c == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+    position
+}
+
+impl<'a> Parser<'a> {
+    /// Add the whitespaces and the new lines from the end of the last token to `offset`.
+    fn gap(&mut self, offset: usize) {
+        while self.end < offset {
+            let rest = &self.source[self.end..offset];
+            let (len, kind) = if rest.starts_with('\n') {
+                (1, TokenKind::Word(Word::NewLine))
+            } else {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                (len, TokenKind::Whitespace)
+            };
+            self.push_trivia(kind, len);
+        }
+    }
+
+    fn push_trivia(&mut self, kind: TokenKind, len: usize) {
+        let span = self.end..self.end + len;
+        let position = self.end_position;
+        self.end_position = advance(position, &self.source[span.clone()]);
+        self.end = span.end;
+        self.trivia.push(Token {
+            kind,
+            span,
+            position,
+        });
+    }
+
+    /// Get the next word, the trivia before are saved.
+    fn fetch(&mut self) -> Result<Option<Token>, ConfigurationError> {
+        loop {
+            let (position, word) = match self.lexer.next() {
+                Some(w) => w,
+                None => {
+                    self.lexer.err()?;
+                    self.gap(self.source.len());
+                    return Ok(None);
+                }
+            };
+            let offset = self.base + self.lexer.offset();
+            self.gap(offset);
+            let len = word.text().len();
+            if word.is_trivia() {
+                self.push_trivia(TokenKind::Word(word), len);
+                continue;
+            }
+
+            self.end = offset + len;
+            self.end_position = advance(position, &self.source[offset..self.end]);
+            self.position = position;
+            return Ok(Some(Token {
+                kind: TokenKind::Word(word),
+                span: offset..self.end,
+                position,
+            }));
+        }
+    }
+
+    /// Get the next word without consuming it.
+    fn peek(&mut self) -> Result<Option<&Word>, ConfigurationError> {
+        if self.peeked.is_none() {
+            self.peeked = self.fetch()?;
+        }
+        Ok(self.peeked.as_ref().map(|t| match &t.kind {
+            TokenKind::Word(w) => w,
+            _ => unreachable!(),
+        }))
+    }
+
+    /// Move the trivia into the node.
+    fn flush(&mut self, node: &mut Node) {
+        node.children
+            .extend(self.trivia.drain(..).map(Element::Token));
+    }
+
+    /// Consume the next word and add it into the node, the end of file is an error.
+    fn take(&mut self, node: &mut Node) -> Result<(Position, Word), ConfigurationError> {
+        let t = match self.peeked.take() {
+            Some(t) => t,
+            None => match self.fetch()? {
+                Some(t) => t,
+                None => return err(self.position, ParserError::UnexpectedEnd),
+            },
+        };
+        self.flush(node);
+        let w = match &t.kind {
+            TokenKind::Word(w) => w.clone(),
+            _ => unreachable!(),
+        };
+        let p = t.position;
+        node.children.push(Element::Token(t));
+        Ok((p, w))
+    }
+
+    fn statement(&mut self) -> Result<Node, ConfigurationError> {
+        let mut node = Node {
+            kind: NodeKind::Root,
+            children: Vec::new(),
+        };
+        match self.take(&mut node)? {
+            (_, w @ (Word::KeywordTag | Word::KeywordSecret)) => {
+                node.kind = if w == Word::KeywordTag {
+                    NodeKind::Tag
+                } else {
+                    NodeKind::Secret
+                };
+                match self.take(&mut node)? {
+                    (_, Word::Variable(_)) => {}
+                    (p, w) => return err(p, ParserError::Unexpected(w)),
+                }
+            }
+            (_, Word::KeywordFile | Word::KeywordDir) => {
+                node.kind = NodeKind::Definition;
+                match self.take(&mut node)? {
+                    (_, Word::SystemPackage | Word::SystemRun | Word::SystemTest) => {}
+                    (_, Word::Variable(_)) => {}
+                    (p, w) => return err(p, ParserError::Unexpected(w)),
+                };
+                let object = self.object()?;
+                node.children.push(Element::Node(object));
+            }
+            (p, w) => return err(p, ParserError::Unexpected(w)),
+        };
+        Ok(node)
+    }
+
+    /// Parse an object and the following pipes and sub path selections.
+    fn object(&mut self) -> Result<Node, ConfigurationError> {
+        let mut object = self.operand()?;
+        // The named inputs must be given to a generator.
+        if object.kind == NodeKind::Inputs
+            && !matches!(self.peek()?, Some(w) if w.pipe_kinds().is_some())
+        {
+            let (p, w) = self.take(&mut object)?;
+            return err(p, ParserError::Unexpected(w));
+        }
+        loop {
+            match self.peek()? {
+                Some(w) if w.pipe_kinds().is_some() => {}
+                Some(Word::Slash) => {
+                    let mut select = Node {
+                        kind: NodeKind::Select,
+                        children: vec![Element::Node(object)],
+                    };
+                    self.take(&mut select)?;
+                    match self.take(&mut select)? {
+                        (_, Word::File(_)) => {}
+                        (p, w) => return err(p, ParserError::Unexpected(w)),
+                    };
+                    object = select;
+                    continue;
+                }
+                _ => break,
+            }
+            let mut pipe = Node {
+                kind: NodeKind::Pipe,
+                children: vec![Element::Node(object)],
+            };
+            self.take(&mut pipe)?;
+            let generator = self.generator()?;
+            pipe.children.push(Element::Node(generator));
+            if let Some(Word::DirectoryComposeOpen) = self.peek()? {
+                let options = self.options()?;
+                pipe.children.push(Element::Node(options));
+            }
+            object = pipe;
+        }
+        Ok(object)
+    }
+
+    /// Parse an object without pipe.
+    fn operand(&mut self) -> Result<Node, ConfigurationError> {
+        let mut node = Node {
+            kind: NodeKind::Value,
+            children: Vec::new(),
+        };
+        match self.take(&mut node)? {
+            (_, Word::File(_) | Word::String(_) | Word::Variable(_)) => {}
+            (_, Word::DirectoryComposeOpen) => {
+                node.kind = NodeKind::Aggregation;
+                self.entries(&mut node, Word::DirectoryComposeClose)?;
+            }
+            (_, Word::DirectoryConcatOpen) => {
+                node.kind = NodeKind::Composition;
+                self.composition(&mut node)?;
+            }
+            (_, Word::ParenthesisOpen) => {
+                node.kind = NodeKind::Inputs;
+                self.entries(&mut node, Word::ParenthesisClose)?;
+            }
+            (_, Word::KeywordOverride) => {
+                match self.take(&mut node)? {
+                    (_, Word::DirectoryConcatOpen) => {}
+                    (p, w) => return err(p, ParserError::Unexpected(w)),
+                };
+                node.kind = NodeKind::Composition;
+                self.composition(&mut node)?;
+            }
+            (p, w) => return err(p, ParserError::Unexpected(w)),
+        };
+        Ok(node)
+    }
+
+    /// Parse the content of `{}` or `()`, after the opening bracket: the
+    /// entries `"key": object` until the `close` bracket.
+    fn entries(&mut self, node: &mut Node, close: Word) -> Result<(), ConfigurationError> {
+        loop {
+            if self.peek()? == Some(&close) {
+                self.take(node)?;
+                return Ok(());
+            }
+            let mut entry = Node {
+                kind: NodeKind::Entry,
+                children: Vec::new(),
+            };
+            match self.take(&mut entry)? {
+                (_, Word::File(_)) => {}
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+            match self.take(&mut entry)? {
+                (_, Word::Colon) => {}
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+            let object = self.object()?;
+            entry.children.push(Element::Node(object));
+            node.children.push(Element::Node(entry));
+            match self.take(node)? {
+                (_, Word::Comma) => {}
+                (_, w) if w == close => return Ok(()),
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+        }
+    }
+
+    /// Parse the options of a generator, `{"name": $"value", ...}`.
+    fn options(&mut self) -> Result<Node, ConfigurationError> {
+        let mut node = Node {
+            kind: NodeKind::Options,
+            children: Vec::new(),
+        };
+        self.take(&mut node)?;
+        loop {
+            if let Some(Word::DirectoryComposeClose) = self.peek()? {
+                self.take(&mut node)?;
+                return Ok(node);
+            }
+            let mut entry = Node {
+                kind: NodeKind::Entry,
+                children: Vec::new(),
+            };
+            match self.take(&mut entry)? {
+                (_, Word::File(_)) => {}
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+            match self.take(&mut entry)? {
+                (_, Word::Colon) => {}
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+            match self.take(&mut entry)? {
+                (_, Word::String(_)) => {}
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+            node.children.push(Element::Node(entry));
+            match self.take(&mut node)? {
+                (_, Word::Comma) => {}
+                (_, Word::DirectoryComposeClose) => return Ok(node),
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+        }
+    }
+
+    /// Parse the content of `[]`, after the opening bracket.
+    fn composition(&mut self, node: &mut Node) -> Result<(), ConfigurationError> {
+        loop {
+            if let Some(Word::DirectoryConcatClose) = self.peek()? {
+                self.take(node)?;
+                return Ok(());
+            }
+            let object = self.object()?;
+            node.children.push(Element::Node(object));
+            match self.take(node)? {
+                (_, Word::Comma) => {}
+                (_, Word::DirectoryConcatClose) => return Ok(()),
+                (p, w) => return err(p, ParserError::Unexpected(w)),
+            };
+        }
+    }
+
+    /// Parse the generator, after the pipe.
+    fn generator(&mut self) -> Result<Node, ConfigurationError> {
+        let mut node = Node {
+            kind: NodeKind::Generator,
+            children: Vec::new(),
+        };
+        match self.take(&mut node)? {
+            (_, Word::File(_)) => {
+                if let Some(Word::DefaultGenerator) = self.peek()? {
+                    self.take(&mut node)?;
+                    match self.take(&mut node)? {
+                        (_, Word::String(_)) => {}
+                        (p, w) => return err(p, ParserError::Unexpected(w)),
+                    }
+                }
+            }
+            (_, Word::String(_) | Word::Variable(_)) => {}
+            (_, Word::ParenthesisOpen) => {
+                let object = self.object()?;
+                node.children.push(Element::Node(object));
+                match self.take(&mut node)? {
+                    (_, Word::ParenthesisClose) => {}
+                    (p, w) => return err(p, ParserError::Unexpected(w)),
+                }
+            }
+            (p, w) => return err(p, ParserError::Unexpected(w)),
+        };
+        Ok(node)
+    }
+}
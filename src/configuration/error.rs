@@ -1,4 +1,4 @@
-use super::{lexer::LexerError, Position};
+use super::{lexer::LexerError, tree::ParserError, Position};
 use std::{error::Error, fmt};
 
 /// Low level error from configuration parsing.
@@ -11,6 +11,11 @@ pub enum ConfigurationError {
     /// An error ocure when tokenize the configuration file.
     Lexer(Position, LexerError),
     // Lexer((Position, LexerError)),
+    /// An error ocure when parse the tokens.
+    Parser(Position, ParserError),
+    /// The tag is a keyword of the new version, the migration can not rename
+    /// it because it is given by the user.
+    TagKeyword(Position, String),
 }
 
 impl fmt::Display for ConfigurationError {
@@ -21,6 +26,14 @@ impl fmt::Display for ConfigurationError {
             Self::Lexer(Position { line, column }, _) => {
                 write!(f, "Lexer error at line {} column {}", line, column)
             }
+            Self::Parser(Position { line, column }, _) => {
+                write!(f, "Parser error at line {} column {}", line, column)
+            }
+            Self::TagKeyword(Position { line, column }, tag) => write!(
+                f,
+                "The tag {:?} at line {} column {} is a keyword of the new version, rename it before",
+                tag, line, column
+            ),
         }
     }
 }
@@ -28,8 +41,9 @@ impl fmt::Display for ConfigurationError {
 impl Error for ConfigurationError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            Self::VersionNotFound | Self::VersionUnknown(_) => None,
+            Self::VersionNotFound | Self::VersionUnknown(_) | Self::TagKeyword(..) => None,
             Self::Lexer(_, err) => Some(err),
+            Self::Parser(_, err) => Some(err),
         }
     }
 }
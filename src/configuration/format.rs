@@ -0,0 +1,310 @@
+//! The canonical formatter of the configuration files.
+
+use super::{lexer::Word, version::Version, ConfigurationError, Tree};
+
+/// Format the configuration file. Comments and blank lines are kept, but several
+/// blank lines are merged. The file must be valid.
+pub fn format(config: &str) -> Result<String, ConfigurationError> {
+    Tree::parse(config)?;
+    let (version, rest, line) = Version::get(config)?;
+    let header = &config[..config.len() - rest.len()];
+    let words: Vec<Word> = version.lexer(rest, line).map(|(_, w)| w).collect();
+    let multiline = multiline_brackets(&words);
+
+    let mut f = Formatter {
+        out: String::from(header),
+        depth: 0,
+        newlines: 0,
+        line_start: true,
+        force_break: false,
+        prev: None,
+        value_end: header.len(),
+        stack: Vec::new(),
+    };
+
+    for (i, w) in words.iter().enumerate() {
+        match w {
+            Word::NewLine => f.newlines += 1,
+            Word::Comment(c) => f.comment(c),
+            Word::Comma => {
+                match words[i + 1..]
+                    .iter()
+                    .enumerate()
+                    .find(|(_, w)| !matches!(w, Word::NewLine | Word::Comment(_)))
+                {
+                    Some((j, w)) if closing(w) && !multiline[i + 1 + j] => {}
+                    _ => f.word(w, false),
+                };
+            }
+            w if opening(w) || closing(w) => f.word(w, multiline[i]),
+            _ => f.word(w, false),
+        }
+    }
+
+    let len = f.out.trim_end().len();
+    f.out.truncate(len);
+    f.out.push('\n');
+    Ok(f.out)
+}
+
+/// For each bracket or parenthesis, true if its content is on several lines.
+fn multiline_brackets(words: &[Word]) -> Vec<bool> {
+    let mut multiline = vec![false; words.len()];
+    let mut stack = Vec::new();
+    for (i, w) in words.iter().enumerate() {
+        match w {
+            w if opening(w) => stack.push(i),
+            w if closing(w) => {
+                if let Some(open) = stack.pop() {
+                    multiline[i] = multiline[open];
+                }
+            }
+            Word::NewLine | Word::Comment(_) => {
+                stack.iter().for_each(|open| multiline[*open] = true)
+            }
+            _ => {}
+        }
+    }
+    multiline
+}
+
+/// True for the words that open a block: the brackets and the parenthesis.
+fn opening(w: &Word) -> bool {
+    matches!(
+        w,
+        Word::DirectoryConcatOpen | Word::DirectoryComposeOpen | Word::ParenthesisOpen
+    )
+}
+
+fn closing(w: &Word) -> bool {
+    matches!(
+        w,
+        Word::DirectoryConcatClose | Word::DirectoryComposeClose | Word::ParenthesisClose
+    )
+}
+
+/// True for the operators after which a definition continues: the pipes, `??`
+/// and `/`.
+fn operator(w: &Word) -> bool {
+    w.pipe_kinds().is_some() || matches!(w, Word::DefaultGenerator | Word::Slash)
+}
+
+struct Formatter<'a> {
+    out: String,
+    /// The indentation level.
+    depth: usize,
+    /// The number of line return before the next word.
+    newlines: usize,
+    /// The next word is the first of the line.
+    line_start: bool,
+    /// Go to a new line before the next word.
+    force_break: bool,
+    /// The previous word, excepted the comments.
+    prev: Option<&'a Word>,
+    /// The index in out just after the previous word.
+    value_end: usize,
+    /// For each opened block, true if its content is on several lines and
+    /// true if it is a list separated by commas, a pipeline is not.
+    stack: Vec<(bool, bool)>,
+}
+
+impl<'a> Formatter<'a> {
+    fn word(&mut self, w: &'a Word, multiline: bool) {
+        let close = closing(w);
+        if close {
+            let (_, list) = self.stack.pop().unwrap_or_default();
+            self.depth -= 1;
+            if multiline {
+                self.force_break = true;
+                if list && !self.prev.is_some_and(|p| p == &Word::Comma || opening(p)) {
+                    self.out.insert(self.value_end, ',');
+                }
+            }
+        } else if self.depth == 0
+            && matches!(
+                w,
+                Word::KeywordTag | Word::KeywordSecret | Word::KeywordFile | Word::KeywordDir
+            )
+        {
+            self.force_break = true;
+        }
+
+        let after_open = self.prev.is_some_and(opening);
+        if self.newlines > 0 || (self.force_break && self.prev.is_some()) {
+            // No blank line just after an opening bracket or before a closing bracket.
+            let max = if close || after_open || self.prev.is_none() {
+                1
+            } else {
+                2
+            };
+            self.line_break(self.newlines.clamp(1, max));
+        }
+
+        if self.line_start {
+            // The operator or the object after it, like after a comment.
+            let continuation = operator(w) || self.prev.is_some_and(operator);
+            for _ in 0..self.depth + continuation as usize {
+                self.out.push('\t');
+            }
+        } else if !after_open
+            && !close
+            && !matches!(w, Word::Comma | Word::Colon | Word::ParenthesisClose)
+            && self.prev != Some(&Word::ParenthesisOpen)
+        {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(&w.text());
+        self.value_end = self.out.len();
+        self.line_start = false;
+        self.force_break = false;
+        self.prev = Some(w);
+
+        match w {
+            w if opening(w) => {
+                self.stack.push((multiline, w != &Word::ParenthesisOpen));
+                self.depth += 1;
+                self.force_break = multiline;
+            }
+            Word::Comma => self.force_break = self.stack.last().is_some_and(|(m, _)| *m),
+            // The named inputs are a list, unlike a pipeline.
+            Word::Colon => {
+                if let Some((_, list)) = self.stack.last_mut() {
+                    *list = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn comment(&mut self, c: &str) {
+        if self.newlines > 0 {
+            let max = if self.prev.is_none() { 1 } else { 2 };
+            self.line_break(self.newlines.min(max));
+        }
+        if self.line_start {
+            for _ in 0..self.depth {
+                self.out.push('\t');
+            }
+        } else {
+            self.out.push(' ');
+        }
+        self.out.push('#');
+        self.out.push_str(c.trim_end());
+        // The lexer includes the line return into the comment.
+        self.newlines = 1;
+        self.force_break = false;
+        self.line_start = false;
+    }
+
+    fn line_break(&mut self, n: usize) {
+        let len = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(len);
+        for _ in 0..n {
+            self.out.push('\n');
+        }
+        self.newlines = 0;
+        self.line_start = true;
+    }
+}
+
+#[test]
+fn test_format() {
+    let config = r##"# Before the header
+CAGE-BUILD-0
+# A comment
+tag   superTag
+
+
+
+dir front "front/">"min"??$"https://exemple.com/minifier"   # minify
+dir$pkg[
+{"file.txt":$"literal \"string\"",
+"b": front},   # comment
+   front>"gen.wasm"
+
+
+]
+dir other [ front , front, ]
+dir long "long"
+> "gen"
+"##;
+    let expected = r##"# Before the header
+CAGE-BUILD-0
+# A comment
+tag superTag
+
+dir front "front/" > "min" ?? $"https://exemple.com/minifier" # minify
+dir $pkg [
+	{
+		"file.txt": $"literal \"string\"",
+		"b": front,
+	}, # comment
+	front > "gen.wasm",
+]
+dir other [front, front]
+dir long "long"
+	> "gen"
+"##;
+    assert_eq!(expected, format(config).unwrap());
+    assert_eq!(expected, format(expected).unwrap());
+
+    assert!(format("CAGE-BUILD-0\ndir a [b]\n").is_err());
+    assert_eq!(
+        "CAGE-BUILD-1\ndir a override [\"a/\", \"b/\"]\n",
+        format("CAGE-BUILD-1\ndir a override[\"a/\",\"b/\",]\n").unwrap()
+    );
+    assert_eq!(
+        "CAGE-BUILD-1\ndir a \"a/\" > \"g\"\n\t/ \"lib/\"\n",
+        format("CAGE-BUILD-1\ndir a \"a/\">\"g\"\n/\"lib/\"\n").unwrap()
+    );
+    assert_eq!(
+        "CAGE-BUILD-1\nfile a (\"src\": \"a/\", \"b\": $\"\") >| \"ld\"\n",
+        format("CAGE-BUILD-1\nfile a ( \"src\":\"a/\" ,\"b\":$\"\" )>|\"ld\"\n").unwrap()
+    );
+    assert_eq!(
+        "CAGE-BUILD-0\nfile a \"a\" | \"cc\" {\"level\": $\"3\"}\n",
+        format("CAGE-BUILD-0\nfile a \"a\"|\"cc\"{ \"level\" :$\"3\", }\n").unwrap()
+    );
+    assert_eq!(
+        "CAGE-BUILD-1\nfile a \"a\" | (\"gen/\" >| \"rustc\") | $\"u\"\n",
+        format("CAGE-BUILD-1\nfile a \"a\"|( \"gen/\">|\"rustc\" )|$\"u\"\n").unwrap()
+    );
+}
+
+#[test]
+fn test_format_inputs_block() {
+    let config = "CAGE-BUILD-1\ndir b \"b/\"\ndir a (\n\"x\": b,\n\"y\": b\n) >> \"g\"\n";
+    let expected = "CAGE-BUILD-1\ndir b \"b/\"\ndir a (\n\t\"x\": b,\n\t\"y\": b,\n) >> \"g\"\n";
+    assert_eq!(expected, format(config).unwrap());
+    assert_eq!(expected, format(expected).unwrap());
+    assert_eq!(
+        "CAGE-BUILD-1\ndir b \"b/\"\ndir a (\"x\": b) >> \"g\"\n",
+        format("CAGE-BUILD-1\ndir b \"b/\"\ndir a (\"x\": b,) >> \"g\"\n").unwrap()
+    );
+}
+
+#[test]
+fn test_format_pipeline_block() {
+    let config = "CAGE-BUILD-1\nfile a \"a\" | (\"gen/\" >| \"rustc\"\n?? $\"u\") | \"strip\"\n";
+    let expected =
+        "CAGE-BUILD-1\nfile a \"a\" | (\n\t\"gen/\" >| \"rustc\"\n\t\t?? $\"u\"\n) | \"strip\"\n";
+    assert_eq!(expected, format(config).unwrap());
+    assert_eq!(expected, format(expected).unwrap());
+    assert_eq!(
+        "CAGE-BUILD-1\nfile a \"a\" | ( # gen\n\t\"gen/\" >| \"rustc\"\n)\n",
+        format("CAGE-BUILD-1\nfile a \"a\" | ( # gen\n\"gen/\" >| \"rustc\")\n").unwrap()
+    );
+}
+
+#[test]
+fn test_format_continuation() {
+    assert_eq!(
+        "CAGE-BUILD-0\ndir a \"a/\" > # c\n\t\"g\"\n",
+        format("CAGE-BUILD-0\ndir a \"a/\" > # c\n\"g\"\n").unwrap()
+    );
+    assert_eq!(
+        "CAGE-BUILD-1\ndir a \"a/\" >\n\t\"g\"\n\t/ \"lib/\"\n",
+        format("CAGE-BUILD-1\ndir a \"a/\" >\n\"g\"\n/\"lib/\"\n").unwrap()
+    );
+}
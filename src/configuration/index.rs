@@ -0,0 +1,201 @@
+//! The semantic index of the variables, to find the references and rename.
+
+use super::{
+    cst::{Cst, Node, NodeKind, Token, TokenKind},
+    lexer::Word,
+    ConfigurationError, Position, Tree,
+};
+use std::{error::Error, fmt};
+
+/// A variable, its definition and its uses.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    /// The variable after the keyword `file` or `dir`.
+    pub definition: Token,
+    /// The uses as an object or as a generator, in the order of the file.
+    pub uses: Vec<Token>,
+}
+
+/// All variables of a configuration file.
+#[derive(Debug, PartialEq)]
+pub struct Index {
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum IndexError {
+    /// The configuration file is invalid.
+    Configuration(ConfigurationError),
+    /// The variable to rename is not defined.
+    Undefined(String),
+    /// The new name is already used by another variable.
+    AlreadyDefined(String),
+    /// The new name can not be read as a variable.
+    InvalidName(String),
+}
+
+impl Index {
+    /// Create the index from a valid configuration file.
+    pub fn new(cst: &Cst) -> Result<Index, ConfigurationError> {
+        Tree::from_cst(cst)?;
+        let mut index = Index {
+            symbols: Vec::new(),
+        };
+        for node in cst.root().nodes() {
+            if node.kind != NodeKind::Definition {
+                continue;
+            }
+            let mut words = node.words().skip(1);
+            if let Some((t, Word::Variable(name))) = words.next() {
+                index.symbols.push(Symbol {
+                    name: name.clone(),
+                    definition: t.clone(),
+                    uses: Vec::new(),
+                });
+            }
+            for child in node.nodes() {
+                index.uses(child);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Add the variables used in the node to their symbol.
+    fn uses(&mut self, node: &Node) {
+        for t in node.tokens() {
+            if let TokenKind::Word(Word::Variable(name)) = &t.kind {
+                // The lowering already checks the variable is defined.
+                if let Some(s) = self.symbols.iter_mut().find(|s| &s.name == name) {
+                    s.uses.push(t.clone());
+                }
+            }
+        }
+    }
+
+    /// Get the symbol of the variable.
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    /// Get the symbol of the variable defined or used at the position.
+    pub fn at(&self, position: Position) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| {
+            std::iter::once(&s.definition)
+                .chain(s.uses.iter())
+                .any(|t| {
+                    t.position.line == position.line
+                        && t.position.column <= position.column
+                        && position.column <= t.position.column + s.name.chars().count()
+                })
+        })
+    }
+
+    /// Iter over the variables, in the order of definition.
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+}
+
+/// Rename the variable `old` to `new` in the configuration file, the layout
+/// and the comments are kept.
+pub fn rename(config: &str, old: &str, new: &str) -> Result<String, IndexError> {
+    let cst = Cst::parse(config).map_err(IndexError::Configuration)?;
+    let index = Index::new(&cst).map_err(IndexError::Configuration)?;
+    let symbol = index
+        .get(old)
+        .ok_or_else(|| IndexError::Undefined(old.to_string()))?;
+
+    match cst.version().lexer(new, 1).collect::<Vec<_>>()[..] {
+        [(_, Word::Variable(ref v))] if v == new => {}
+        _ => return Err(IndexError::InvalidName(new.to_string())),
+    };
+    if old != new && index.get(new).is_some() {
+        return Err(IndexError::AlreadyDefined(new.to_string()));
+    }
+
+    let mut out = String::with_capacity(config.len());
+    let mut end = 0;
+    for t in std::iter::once(&symbol.definition).chain(symbol.uses.iter()) {
+        out.push_str(&config[end..t.span.start]);
+        out.push_str(new);
+        end = t.span.end;
+    }
+    out.push_str(&config[end..]);
+    Ok(out)
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Configuration(e) => write!(f, "{}", e),
+            Self::Undefined(v) => write!(f, "The variable {:?} is not defined", v),
+            Self::AlreadyDefined(v) => write!(f, "The variable {:?} is already defined", v),
+            Self::InvalidName(v) => write!(f, "{:?} is not a valid variable name", v),
+        }
+    }
+}
+
+impl Error for IndexError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Configuration(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_index() {
+    let config = "CAGE-BUILD-1
+tag front # not a variable
+dir front \"front/\" > \"min\"
+file été front | \"gen\"
+dir $pkg [front, été |> \"a\"] > front # generator
+";
+    let cst = Cst::parse(config).unwrap();
+    let index = Index::new(&cst).unwrap();
+    let p = |line, column| Position { line, column };
+
+    assert_eq!(
+        vec!["front", "été"],
+        index.symbols().map(|s| s.name.as_str()).collect::<Vec<_>>()
+    );
+    let front = index.get("front").unwrap();
+    assert_eq!(p(3, 5), front.definition.position);
+    assert_eq!(
+        vec![p(4, 10), p(5, 11), p(5, 32)],
+        front.uses.iter().map(|t| t.position).collect::<Vec<_>>()
+    );
+    assert_eq!(Some(front), index.at(p(5, 13)));
+    assert_eq!(Some("été"), index.at(p(5, 18)).map(|s| s.name.as_str()));
+    assert_eq!(None, index.at(p(2, 5)));
+    assert_eq!(None, index.get("pkg"));
+
+    assert_eq!(
+        Ok("CAGE-BUILD-1
+tag front # not a variable
+dir back \"front/\" > \"min\"
+file été back | \"gen\"
+dir $pkg [back, été |> \"a\"] > back # generator
+"
+        .to_string()),
+        rename(config, "front", "back")
+    );
+    assert_eq!(
+        Err(IndexError::Undefined("back".to_string())),
+        rename(config, "back", "b")
+    );
+    assert_eq!(
+        Err(IndexError::AlreadyDefined("été".to_string())),
+        rename(config, "front", "été")
+    );
+    assert_eq!(
+        Err(IndexError::InvalidName("dir".to_string())),
+        rename(config, "front", "dir")
+    );
+    assert_eq!(
+        Err(IndexError::InvalidName("a b".to_string())),
+        rename(config, "front", "a b")
+    );
+}
@@ -0,0 +1,182 @@
+//! Analysis of a configuration file for the language server.
+
+use super::{
+    cst::Cst, index::Index, lexer::Word, version::Version, ConfigurationError, Position, Tree,
+};
+use std::error::Error;
+
+/// An error found in the configuration file.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub position: Position,
+    pub message: String,
+}
+
+/// A completion item.
+#[derive(Debug, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompletionKind {
+    Keyword,
+    System,
+    Variable,
+}
+
+const SYSTEMS: [&str; 3] = ["$pkg", "$run", "$test"];
+
+/// Get the errors of the configuration file.
+pub fn diagnostics(config: &str) -> Vec<Diagnostic> {
+    let e = match Tree::parse(config) {
+        Ok(_) => return Vec::new(),
+        Err(e) => e,
+    };
+    let position = match &e {
+        ConfigurationError::Lexer(p, _)
+        | ConfigurationError::Parser(p, _)
+        | ConfigurationError::TagKeyword(p, _) => *p,
+        ConfigurationError::VersionNotFound | ConfigurationError::VersionUnknown(_) => {
+            Position { line: 1, column: 1 }
+        }
+    };
+    let message = match e.source() {
+        Some(s) => format!("{}: {}", e, s),
+        None => e.to_string(),
+    };
+    vec![Diagnostic { position, message }]
+}
+
+/// Get the position and the length in chars of the definition of the variable
+/// at `position`, from the [`Index`] of a valid configuration file.
+pub fn definition(config: &str, position: Position) -> Option<(Position, usize)> {
+    let index = Index::new(&Cst::parse(config).ok()?).ok()?;
+    let symbol = index.at(position)?;
+    Some((symbol.definition.position, symbol.name.chars().count()))
+}
+
+/// Get the documentation of the system variable at `position`.
+pub fn hover(config: &str, position: Position) -> Option<&'static str> {
+    match word_at(&tokens(config), position)? {
+        Word::SystemPackage => {
+            Some("`$pkg`: the system variable for the package, the final artifact of the project.")
+        }
+        Word::SystemRun => Some("`$run`: the system variable for the executable binary."),
+        Word::SystemTest => Some("`$test`: the system variable for the executable test."),
+        _ => None,
+    }
+}
+
+/// Get the keywords of the version, the system variables and the defined variables.
+pub fn completion(config: &str) -> Vec<Completion> {
+    let tokens = tokens(config);
+    let item = |label: &str, kind| Completion {
+        label: label.to_string(),
+        kind,
+    };
+    let version = Version::get(config).map_or(Version::LATEST, |(v, _, _)| v);
+    version
+        .keywords()
+        .iter()
+        .map(|k| item(k, CompletionKind::Keyword))
+        .chain(SYSTEMS.iter().map(|s| item(s, CompletionKind::System)))
+        .chain(definitions(&tokens).map(|(_, v)| item(v, CompletionKind::Variable)))
+        .collect()
+}
+
+/// Get the tokens of the configuration, until the first lexer error.
+fn tokens(config: &str) -> Vec<(Position, Word)> {
+    match Version::get(config) {
+        Ok((version, rest, line)) => version.lexer(rest, line).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Get the position and the name of defined variables.
+fn definitions(tokens: &[(Position, Word)]) -> impl Iterator<Item = (Position, &str)> {
+    tokens.windows(2).filter_map(|w| match w {
+        [(_, Word::KeywordFile | Word::KeywordDir), (p, Word::Variable(v))] => {
+            Some((*p, v.as_str()))
+        }
+        _ => None,
+    })
+}
+
+/// Get the variable or the system variable at the position, the position can be
+/// just after the last char.
+fn word_at(tokens: &[(Position, Word)], position: Position) -> Option<Word> {
+    tokens.iter().find_map(|(p, w)| {
+        let len = match w {
+            Word::Variable(v) => v.chars().count(),
+            Word::SystemPackage | Word::SystemRun => 4,
+            Word::SystemTest => 5,
+            _ => return None,
+        };
+        if p.line == position.line
+            && p.column <= position.column
+            && position.column <= p.column + len
+        {
+            Some(w.clone())
+        } else {
+            None
+        }
+    })
+}
+
+#[test]
+fn test_language() {
+    let config = "CAGE-BUILD-0
+dir front \"front/\" > \"min\" ?? $\"https://exemple.com/minifier\"
+dir $pkg [ front ]
+";
+    let p = |line, column| Position { line, column };
+
+    assert_eq!(Vec::<Diagnostic>::new(), diagnostics(config));
+    assert_eq!(
+        vec![Diagnostic {
+            position: p(2, 8),
+            message: "Parser error at line 2 column 8: The variable \"back\" is not defined"
+                .to_string(),
+        }],
+        diagnostics("CAGE-BUILD-0\ndir a [back]\n")
+    );
+    assert_eq!(
+        vec![Diagnostic {
+            position: p(1, 1),
+            message: "Version not found".to_string(),
+        }],
+        diagnostics("")
+    );
+
+    assert_eq!(Some((p(2, 5), 5)), definition(config, p(3, 12)));
+    assert_eq!(Some((p(2, 5), 5)), definition(config, p(3, 17)));
+    assert_eq!(Some((p(2, 5), 5)), definition(config, p(2, 7)));
+    assert_eq!(None, definition(config, p(3, 18)));
+    assert_eq!(None, definition(config, p(3, 2)));
+    assert_eq!(None, definition("CAGE-BUILD-0\ndir a [b]\n", p(2, 5)));
+
+    assert!(hover(config, p(3, 6)).unwrap().starts_with("`$pkg`"));
+    assert_eq!(None, hover(config, p(3, 12)));
+
+    let labels: Vec<(String, CompletionKind)> = completion(config)
+        .into_iter()
+        .map(|c| (c.label, c.kind))
+        .collect();
+    assert_eq!(
+        vec![
+            ("dir".to_string(), CompletionKind::Keyword),
+            ("file".to_string(), CompletionKind::Keyword),
+            ("tag".to_string(), CompletionKind::Keyword),
+            ("$pkg".to_string(), CompletionKind::System),
+            ("$run".to_string(), CompletionKind::System),
+            ("$test".to_string(), CompletionKind::System),
+            ("front".to_string(), CompletionKind::Variable),
+        ],
+        labels
+    );
+    assert!(completion("CAGE-BUILD-1\n")
+        .iter()
+        .any(|c| c.label == "secret" && c.kind == CompletionKind::Keyword));
+}
@@ -5,14 +5,27 @@ pub struct CharItem<'a> {
     chars_iter: Chars<'a>,
     line: usize,
     column: usize,
+    /// The position of the last returned char.
+    previous: Position,
+    /// The byte offset of the next char.
+    offset: usize,
+    /// The byte offset of the last returned char.
+    previous_offset: usize,
 }
 
 impl<'a> CharItem<'a> {
     pub fn new(s: &'a str) -> Self {
+        Self::with_line(s, 1)
+    }
+    /// Create an iterator where `s` begins at the line `line`.
+    pub fn with_line(s: &'a str, line: usize) -> Self {
         Self {
             chars_iter: s.chars(),
-            line: 1,
+            line,
             column: 1,
+            previous: Position { line, column: 1 },
+            offset: 0,
+            previous_offset: 0,
         }
     }
     pub fn position(&self) -> Position {
@@ -21,12 +34,34 @@ impl<'a> CharItem<'a> {
             column: self.column,
         }
     }
+    /// The position of the last returned char.
+    pub fn previous(&self) -> Position {
+        self.previous
+    }
+    /// The byte offset of the next char.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    /// The byte offset of the last returned char.
+    pub fn previous_offset(&self) -> usize {
+        self.previous_offset
+    }
+    /// Get the next char without consuming it.
+    pub fn peek(&self) -> Option<char> {
+        self.chars_iter.clone().next()
+    }
 }
 
 impl<'a> Iterator for CharItem<'a> {
     type Item = char;
     fn next(&mut self) -> Option<Self::Item> {
+        self.previous = Position {
+            line: self.line,
+            column: self.column,
+        };
+        self.previous_offset = self.offset;
         let next = self.chars_iter.next();
+        self.offset += next.map_or(0, char::len_utf8);
         match next {
             Some('\n') => {
                 self.line += 1;
@@ -44,6 +79,7 @@ impl<'a> Iterator for CharItem<'a> {
 #[test]
 fn test_char_iter() {
     let mut iter = CharItem::new("Hel\nlo");
+    assert_eq!(0, iter.offset());
     assert_eq!(Some('H'), iter.next());
     assert_eq!(Position { line: 1, column: 2 }, iter.position());
     assert_eq!(Some('e'), iter.next());
@@ -52,6 +88,9 @@ fn test_char_iter() {
     assert_eq!(Position { line: 1, column: 4 }, iter.position());
     assert_eq!(Some('\n'), iter.next());
     assert_eq!(Position { line: 2, column: 1 }, iter.position());
+    assert_eq!(Position { line: 1, column: 4 }, iter.previous());
+    assert_eq!(3, iter.previous_offset());
+    assert_eq!(4, iter.offset());
     assert_eq!(Some('l'), iter.next());
     assert_eq!(Position { line: 2, column: 2 }, iter.position());
     assert_eq!(Some('o'), iter.next());
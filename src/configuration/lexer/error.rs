@@ -9,12 +9,18 @@ pub enum LexerError {
     StringWithoutEnd,
     /// A half or default generator symbol (just one `?`).
     HalfDefaultGenerator,
+    /// A half map pipe (a `*` without `|`).
+    HalfPipeEach,
     /// A unknown system variable.
     UnknowSystem(String),
     /// Double dollard, unknoow token.
     DoubleDollard,
     /// A dollard at end of the configuration file, expected a literal string or a system variable.
     DollardAtEOF,
+    /// An unknown escape sequence in a file path or a literal string, the char after the backslash.
+    InvalidEscape(char),
+    /// A `\u{...}` escape without a valid unicode scalar value.
+    InvalidUnicode(String),
 }
 
 impl fmt::Display for LexerError {
@@ -27,9 +33,12 @@ impl fmt::Display for LexerError {
             ),
             LexerError::StringWithoutEnd => f.write_str("A not closed file path or literal string"),
             LexerError::HalfDefaultGenerator => f.write_str("A single '?', unknown this symbol (maybe '??')."),
+			LexerError::HalfPipeEach => f.write_str("A single '*', unknown this symbol (maybe '*|')."),
 			LexerError::UnknowSystem(v) => write!(f, "Unknown the {:?} system variable", v),
 			LexerError::DoubleDollard => f.write_str("Double dollard, unknown this token"),
 			LexerError::DollardAtEOF => f.write_str("A dollard at end of the configuration file, expected a literal string or a system variable."),
+			LexerError::InvalidEscape(c) => write!(f, "Unknown the escape sequence \\{}, expected \\\", \\\\, \\n, \\t or \\u{{...}}", c),
+			LexerError::InvalidUnicode(s) => write!(f, "Invalid unicode escape \\u{{{}}}", s),
         }
     }
 }
@@ -0,0 +1,67 @@
+use super::LexerError;
+
+/// Unescape a file path or a literal string, without the limit quotes. The
+/// escapes are `\"`, `\\`, `\n`, `\t` and `\u{...}` with 1 to 6 hexadecimal
+/// digits. An error is given with the byte offset of the backslash.
+pub fn unescape(s: &str) -> Result<String, (usize, LexerError)> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let offset = s.len() - chars.as_str().len() - 1;
+        let err = |e| Err((offset, e));
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('u') => {
+                let rest = chars.as_str();
+                let (hex, after) = match rest.strip_prefix('{').and_then(|r| r.split_once('}')) {
+                    Some(h) => h,
+                    None => return err(LexerError::InvalidUnicode(rest.chars().take(8).collect())),
+                };
+                let c = match u32::from_str_radix(hex, 16) {
+                    Ok(n) if !hex.is_empty() && hex.len() <= 6 => char::from_u32(n),
+                    _ => None,
+                };
+                match c {
+                    Some(c) => out.push(c),
+                    None => return err(LexerError::InvalidUnicode(hex.to_string())),
+                }
+                chars = after.chars();
+            }
+            Some(c) => return err(LexerError::InvalidEscape(c)),
+            None => return err(LexerError::StringWithoutEnd),
+        }
+    }
+    Ok(out)
+}
+
+#[test]
+fn test_unescape() {
+    assert_eq!(
+        Ok("a \"b\" \\ \n\t é ☃".to_string()),
+        unescape(r#"a \"b\" \\ \n\t \u{e9} \u{2603}"#)
+    );
+    assert_eq!(Err((2, LexerError::InvalidEscape('x'))), unescape(r"é\x"));
+    assert_eq!(
+        Err((0, LexerError::InvalidUnicode("d800".to_string()))),
+        unescape(r"\u{d800}")
+    );
+    assert_eq!(
+        Err((0, LexerError::InvalidUnicode("1234567".to_string()))),
+        unescape(r"\u{1234567}")
+    );
+    assert_eq!(
+        Err((0, LexerError::InvalidUnicode("".to_string()))),
+        unescape(r"\u{}")
+    );
+    assert_eq!(
+        Err((0, LexerError::InvalidUnicode("41".to_string()))),
+        unescape(r"\u41")
+    );
+}
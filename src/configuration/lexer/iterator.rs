@@ -1,5 +1,5 @@
 use super::super::ConfigurationError;
-use super::{CharItem, Lexer, LexerError, Position, Word};
+use super::{unescape, CharItem, Lexer, LexerError, Position, Version, Word};
 
 /// The state of the lexer.
 #[derive(Debug, Copy, Clone)]
@@ -16,6 +16,8 @@ pub enum State {
     FileEscape,
     /// Begin of one question mark.
     QuestionMark,
+    /// Begin of the map pipe `*|`.
+    Star,
     /// Just after a dollar
     Dollar,
     /// inside a system variable
@@ -32,31 +34,83 @@ impl<'a> Iterator for Lexer<'a> {
         if self.error.is_some() {
             return None;
         } else if let Some(r) = self.comming.take() {
-            return Some((self.chars.position(), r));
+            self.start = self.comming_position;
+            self.start_offset = self.comming_offset;
+            let r = self.pipe(r);
+            return Some((self.comming_position, r));
         }
         self.buff.clear();
-        let p = self.chars.position();
-        self.word_lexer().map(|w| (p, w))
+        self.escapes.clear();
+        // A state other than initial is set by the last char, so the word begin with it.
+        let (start, start_offset) = match self.state {
+            State::Initial => (self.chars.position(), self.chars.offset()),
+            _ => (self.chars.previous(), self.chars.previous_offset()),
+        };
+        self.start = start;
+        self.start_offset = start_offset;
+        let w = self.word_lexer();
+        self.comming_position = self.chars.previous();
+        self.comming_offset = self.chars.previous_offset();
+        w.map(|w| (self.start, w))
     }
 }
 
 impl<'a> Lexer<'a> {
-    /// Create a new Lexer for the config.
+    /// Create a new Lexer for the config, with the newest grammar.
     pub fn new(config: &'a str) -> Self {
+        Self::with_version(config, 1, Version::LATEST)
+    }
+
+    /// Create a new Lexer for the config that begins at the line `line`, used
+    /// after the header, with the grammar of `version`.
+    pub fn with_version(config: &'a str, line: usize, version: Version) -> Self {
         Self {
-            chars: CharItem::new(config),
+            chars: CharItem::with_line(config, line),
             state: State::Initial,
             buff: String::new(),
             comming: None,
+            comming_position: Position { line, column: 1 },
+            comming_offset: 0,
+            start: Position { line, column: 1 },
+            start_offset: 0,
             error: None,
+            escapes: Vec::new(),
+            version,
         }
     }
 
-    /// Get a Word from self.buffer, return a keyword or a variable. Always `Some(Ok(_))`.
+    /// The byte offset of the first char of the last returned word.
+    pub fn offset(&self) -> usize {
+        self.start_offset
+    }
+
+    /// After a single pipe char, consume the second char of a two chars pipe.
+    fn pipe(&mut self, first: Word) -> Word {
+        if self.version == Version::V0 {
+            return first;
+        }
+        let w = match (&first, self.chars.peek()) {
+            (Word::PipeDirectory, Some('>')) => Word::PipeDirectoryToDirectory,
+            (Word::PipeDirectory, Some('|')) => Word::PipeDirectoryToFile,
+            (Word::PipeFile, Some('>')) => Word::PipeFileToDirectory,
+            (Word::PipeFile, Some('|')) => Word::PipeFileToFile,
+            _ => return first,
+        };
+        self.chars.next();
+        w
+    }
+
+    /// Get a Word from self.buffer, return a keyword of the version or a variable.
+    /// Always `Some(Ok(_))`.
     fn type_word(&self) -> Option<Word> {
+        if !self.version.keywords().contains(&&self.buff[..]) {
+            return Some(Word::Variable(self.buff.clone()));
+        }
         Some(match &self.buff[..] {
             "dir" => Word::KeywordDir,
             "file" => Word::KeywordFile,
+            "override" => Word::KeywordOverride,
+            "secret" => Word::KeywordSecret,
             "tag" => Word::KeywordTag,
             _ => Word::Variable(self.buff.clone()),
         })
@@ -77,35 +131,71 @@ impl<'a> Lexer<'a> {
 
     // Save error, and return None.
     fn set_err(&mut self, e: LexerError) {
-        self.error = Some(e);
+        self.error = Some((self.chars.position(), e));
         self.state = State::Initial;
     }
 
+    /// Check the escapes of the file path or the string in `self.buff`, an
+    /// error is at the backslash of the invalid escape. CAGE-BUILD-0 has no
+    /// escapes.
+    fn check_escapes(&mut self) -> bool {
+        if self.version == Version::V0 {
+            return true;
+        }
+        match unescape(&self.buff) {
+            Ok(_) => true,
+            Err((offset, e)) => {
+                let position = self
+                    .escapes
+                    .iter()
+                    .find(|(o, _)| *o == offset)
+                    .map_or_else(|| self.chars.position(), |(_, p)| *p);
+                self.error = Some((position, e));
+                self.state = State::Initial;
+                false
+            }
+        }
+    }
+
     /// If an error occure, take it and return the error into [`ConfigurationError::Lexer`].
-    pub fn err(self) -> Result<(), ConfigurationError> {
-        match self.error {
+    pub fn err(&mut self) -> Result<(), ConfigurationError> {
+        match self.error.take() {
             None => Ok(()),
-            Some(e) => Err(ConfigurationError::Lexer(self.chars.position(), e)),
+            Some((position, e)) => Err(ConfigurationError::Lexer(position, e)),
         }
     }
 
     /// Fill self.buff and self.comming
     fn word_lexer(&mut self) -> Option<Word> {
         match (self.state, self.chars.next()) {
+            // The operators added by CAGE-BUILD-1.
+            (State::Initial | State::Word | State::System, Some(c @ ('(' | ')' | '/' | '*')))
+                if self.version == Version::V0 =>
+            {
+                self.set_err(LexerError::UnknowChar(c));
+                return None;
+            }
             (State::Initial, None) => return None,
-            (State::Initial, Some(' ' | '\t')) => {}
+            (State::Initial, Some(' ' | '\t')) => {
+                self.start = self.chars.position();
+                self.start_offset = self.chars.offset();
+            }
             (State::Initial, Some('\n')) => return Some(Word::NewLine),
             (State::Initial, Some('[')) => return Some(Word::DirectoryConcatOpen),
             (State::Initial, Some(']')) => return Some(Word::DirectoryConcatClose),
+            (State::Initial, Some('(')) => return Some(Word::ParenthesisOpen),
+            (State::Initial, Some(')')) => return Some(Word::ParenthesisClose),
             (State::Initial, Some('{')) => return Some(Word::DirectoryComposeOpen),
             (State::Initial, Some('}')) => return Some(Word::DirectoryComposeClose),
             (State::Initial, Some(':')) => return Some(Word::Colon),
             (State::Initial, Some(',')) => return Some(Word::Comma),
-            (State::Initial, Some('|')) => return Some(Word::PipeFile),
-            (State::Initial, Some('>')) => return Some(Word::PipeDirectory),
+            (State::Initial, Some('/')) => return Some(Word::Slash),
+            (State::Initial, Some('|')) => return Some(self.pipe(Word::PipeFile)),
+            (State::Initial, Some('>')) => return Some(self.pipe(Word::PipeDirectory)),
             (State::Initial, Some('#')) => self.state = State::Comment,
             (State::Initial, Some('"')) => self.state = State::File,
             (State::Initial, Some('?')) => self.state = State::QuestionMark,
+            (State::Initial, Some('*')) => self.state = State::Star,
             (State::Initial, Some('$')) => self.state = State::Dollar,
 
             (State::QuestionMark, Some('?')) => {
@@ -117,6 +207,15 @@ impl<'a> Lexer<'a> {
                 return None;
             }
 
+            (State::Star, Some('|')) => {
+                self.state = State::Initial;
+                return Some(Word::PipeEach);
+            }
+            (State::Star, _) => {
+                self.set_err(LexerError::HalfPipeEach);
+                return None;
+            }
+
             (State::Comment, None | Some('\n')) => {
                 self.state = State::Initial;
                 return Some(Word::Comment(self.buff.clone()));
@@ -125,9 +224,15 @@ impl<'a> Lexer<'a> {
 
             (State::File, Some('"')) => {
                 self.state = State::Initial;
+                if !self.check_escapes() {
+                    return None;
+                }
                 return Some(Word::File(self.buff.clone()));
             }
-            (State::File, Some('\\')) => self.state = State::FileEscape,
+            (State::File, Some('\\')) => {
+                self.escapes.push((self.buff.len(), self.chars.previous()));
+                self.state = State::FileEscape;
+            }
             (State::File, Some(c)) => self.buff.push(c),
             (State::File | State::FileEscape, None) => {
                 self.set_err(LexerError::StringWithoutEnd);
@@ -157,6 +262,16 @@ impl<'a> Lexer<'a> {
                 self.comming = Some(Word::DirectoryConcatClose);
                 return self.type_word();
             }
+            (State::Word, Some('(')) => {
+                self.state = State::Initial;
+                self.comming = Some(Word::ParenthesisOpen);
+                return self.type_word();
+            }
+            (State::Word, Some(')')) => {
+                self.state = State::Initial;
+                self.comming = Some(Word::ParenthesisClose);
+                return self.type_word();
+            }
             (State::Word, Some('{')) => {
                 self.state = State::Initial;
                 self.comming = Some(Word::DirectoryComposeOpen);
@@ -172,6 +287,11 @@ impl<'a> Lexer<'a> {
                 self.comming = Some(Word::Colon);
                 return self.type_word();
             }
+            (State::Word, Some('/')) => {
+                self.state = State::Initial;
+                self.comming = Some(Word::Slash);
+                return self.type_word();
+            }
             (State::Word, Some(',')) => {
                 self.state = State::Initial;
                 self.comming = Some(Word::Comma);
@@ -191,6 +311,10 @@ impl<'a> Lexer<'a> {
                 self.state = State::QuestionMark;
                 return self.type_word();
             }
+            (State::Word, Some('*')) => {
+                self.state = State::Star;
+                return self.type_word();
+            }
             (State::Word, None | Some(' ' | '\t')) => {
                 self.state = State::Initial;
                 return self.type_word();
@@ -227,6 +351,16 @@ impl<'a> Lexer<'a> {
                 self.comming = Some(Word::DirectoryConcatClose);
                 return self.type_system();
             }
+            (State::System, Some('(')) => {
+                self.state = State::Initial;
+                self.comming = Some(Word::ParenthesisOpen);
+                return self.type_system();
+            }
+            (State::System, Some(')')) => {
+                self.state = State::Initial;
+                self.comming = Some(Word::ParenthesisClose);
+                return self.type_system();
+            }
             (State::System, Some('{')) => {
                 self.state = State::Initial;
                 self.comming = Some(Word::DirectoryComposeOpen);
@@ -242,6 +376,11 @@ impl<'a> Lexer<'a> {
                 self.comming = Some(Word::Colon);
                 return self.type_system();
             }
+            (State::System, Some('/')) => {
+                self.state = State::Initial;
+                self.comming = Some(Word::Slash);
+                return self.type_system();
+            }
             (State::System, Some(',')) => {
                 self.state = State::Initial;
                 self.comming = Some(Word::Comma);
@@ -261,6 +400,10 @@ impl<'a> Lexer<'a> {
                 self.state = State::QuestionMark;
                 return self.type_system();
             }
+            (State::System, Some('*')) => {
+                self.state = State::Star;
+                return self.type_system();
+            }
             (State::System, None | Some(' ' | '\t')) => {
                 self.state = State::Initial;
                 return self.type_system();
@@ -296,9 +439,15 @@ impl<'a> Lexer<'a> {
 
             (State::String, Some('"')) => {
                 self.state = State::Initial;
+                if !self.check_escapes() {
+                    return None;
+                }
                 return Some(Word::String(self.buff.clone()));
             }
-            (State::String, Some('\\')) => self.state = State::StringEscape,
+            (State::String, Some('\\')) => {
+                self.escapes.push((self.buff.len(), self.chars.previous()));
+                self.state = State::StringEscape;
+            }
             (State::String, Some(c)) => self.buff.push(c),
             (State::String | State::StringEscape, None) => {
                 self.set_err(LexerError::StringWithoutEnd);
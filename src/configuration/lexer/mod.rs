@@ -1,10 +1,12 @@
 mod char_iter;
 mod error;
+mod escape;
 mod iterator;
 
-use super::Position;
+use super::{version::Version, Position};
 use char_iter::CharItem;
 pub use error::LexerError;
+pub use escape::unescape;
 use iterator::State;
 
 /// The Lexer, split the input into [`Word`]. It's an iterator.
@@ -15,12 +17,23 @@ pub struct Lexer<'a> {
     buff: String,
     /// For founded element, send at the comming call of `next` method.
     comming: Option<Word>,
-    /// The founed error.
-    error: Option<LexerError>,
+    /// The position and the byte offset of the comming element.
+    comming_position: Position,
+    comming_offset: usize,
+    /// The position and the byte offset of the first char of the current word.
+    start: Position,
+    start_offset: usize,
+    /// The founed error and its position.
+    error: Option<(Position, LexerError)>,
+    /// The byte offset in `buff` and the position of each backslash of the
+    /// current file path or string.
+    escapes: Vec<(usize, Position)>,
+    /// The version of the grammar, the first one does not have all operators.
+    version: Version,
 }
 
 /// One lexer token. Created with [`Lexer.next()`].
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Word {
     /// "tag" keyword
     KeywordTag,
@@ -28,6 +41,10 @@ pub enum Word {
     KeywordFile,
     /// "file" keyword
     KeywordDir,
+    /// "secret" keyword
+    KeywordSecret,
+    /// "override" keyword, before a composition where the last directory wins.
+    KeywordOverride,
 
     /// The system variable for package, `$pkg`.
     SystemPackage,
@@ -39,10 +56,11 @@ pub enum Word {
     /// One variable.
     Variable(String),
 
-    /// File path. It does not contain the limit quotes but it is not unescaped.
+    /// File path. It does not contain the limit quotes but it is not unescaped,
+    /// the escapes are checked by the lexer, see [`unescape`].
     File(String),
     /// A literal stringn, can be an url or a value used as a file content.
-    /// It does not contain the limit quotes but it is not unescaped.
+    /// It does not contain the limit quotes but it is not unescaped, like [`Word::File`].
     String(String),
 
     /// Colon, to separate the key and the value, in directory.
@@ -51,10 +69,23 @@ pub enum Word {
     Comma,
     /// The default generator operator, `??`
     DefaultGenerator,
+    /// The operator to select a sub path of a directory, `/`.
+    Slash,
     /// The pipe to a file.
     PipeFile,
     /// The pipe to a directory
     PipeDirectory,
+    /// The pipe from a directory to a directory, `>>`.
+    PipeDirectoryToDirectory,
+    /// The pipe from a directory to a file, `>|`.
+    PipeDirectoryToFile,
+    /// The pipe from a file to a directory, `|>`.
+    PipeFileToDirectory,
+    /// The pipe from a file to a file, `||`.
+    PipeFileToFile,
+    /// The pipe from a directory to a directory, the generator is called for
+    /// each file, `*|`.
+    PipeEach,
     /// Opening Symbol for composie a directory. `{`
     DirectoryComposeOpen,
     /// Closing Symbol for composie a directory. `}`
@@ -63,6 +94,12 @@ pub enum Word {
     DirectoryConcatOpen,
     /// Closing Symbol for concatenation a directory. `]`
     DirectoryConcatClose,
+    /// Opening Symbol for a pipeline used as a generator, or for the named
+    /// inputs of a generator. `(`
+    ParenthesisOpen,
+    /// Closing Symbol for a pipeline used as a generator, or for the named
+    /// inputs of a generator. `)`
+    ParenthesisClose,
 
     /// A comment, used to format the build config file.
     /// Do not contain the hash `#` and the line return.
@@ -71,6 +108,59 @@ pub enum Word {
     NewLine,
 }
 
+impl Word {
+    /// For a pipe operator, get if the input is a directory (None if the operator
+    /// does not say it) and if the output is a directory.
+    pub fn pipe_kinds(&self) -> Option<(Option<bool>, bool)> {
+        match self {
+            Self::PipeFile => Some((None, false)),
+            Self::PipeDirectory => Some((None, true)),
+            Self::PipeDirectoryToDirectory => Some((Some(true), true)),
+            Self::PipeDirectoryToFile => Some((Some(true), false)),
+            Self::PipeFileToDirectory => Some((Some(false), true)),
+            Self::PipeFileToFile => Some((Some(false), false)),
+            Self::PipeEach => Some((Some(true), true)),
+            _ => None,
+        }
+    }
+
+    /// Get the source of the word.
+    pub fn text(&self) -> String {
+        match self {
+            Self::KeywordTag => "tag".to_string(),
+            Self::KeywordFile => "file".to_string(),
+            Self::KeywordDir => "dir".to_string(),
+            Self::KeywordSecret => "secret".to_string(),
+            Self::KeywordOverride => "override".to_string(),
+            Self::SystemPackage => "$pkg".to_string(),
+            Self::SystemRun => "$run".to_string(),
+            Self::SystemTest => "$test".to_string(),
+            Self::Variable(v) => v.clone(),
+            Self::File(f) => format!("\"{}\"", f),
+            Self::String(s) => format!("$\"{}\"", s),
+            Self::Colon => ":".to_string(),
+            Self::Comma => ",".to_string(),
+            Self::DefaultGenerator => "??".to_string(),
+            Self::Slash => "/".to_string(),
+            Self::PipeFile => "|".to_string(),
+            Self::PipeDirectory => ">".to_string(),
+            Self::PipeDirectoryToDirectory => ">>".to_string(),
+            Self::PipeDirectoryToFile => ">|".to_string(),
+            Self::PipeFileToDirectory => "|>".to_string(),
+            Self::PipeFileToFile => "||".to_string(),
+            Self::PipeEach => "*|".to_string(),
+            Self::DirectoryComposeOpen => "{".to_string(),
+            Self::DirectoryComposeClose => "}".to_string(),
+            Self::DirectoryConcatOpen => "[".to_string(),
+            Self::DirectoryConcatClose => "]".to_string(),
+            Self::ParenthesisOpen => "(".to_string(),
+            Self::ParenthesisClose => ")".to_string(),
+            Self::Comment(c) => format!("#{}", c),
+            Self::NewLine => "\n".to_string(),
+        }
+    }
+}
+
 #[test]
 fn test_lexer() {
     let mut l = Lexer::new(
@@ -142,4 +232,94 @@ Enclose by double quote \"\".",
 
     assert_eq!(None, l.next());
     assert_eq!(Ok(()), l.err());
+
+    let words: Vec<Word> = Lexer::new("a>>b >| c|>d || e | > f*|g *| h")
+        .map(|(_, w)| w)
+        .collect();
+    assert_eq!(
+        vec![
+            Word::Variable("a".to_string()),
+            Word::PipeDirectoryToDirectory,
+            Word::Variable("b".to_string()),
+            Word::PipeDirectoryToFile,
+            Word::Variable("c".to_string()),
+            Word::PipeFileToDirectory,
+            Word::Variable("d".to_string()),
+            Word::PipeFileToFile,
+            Word::Variable("e".to_string()),
+            Word::PipeFile,
+            Word::PipeDirectory,
+            Word::Variable("f".to_string()),
+            Word::PipeEach,
+            Word::Variable("g".to_string()),
+            Word::PipeEach,
+            Word::Variable("h".to_string()),
+        ],
+        words
+    );
+
+    let words: Vec<Word> = Lexer::new("a|(b|$\"c\")|(d)/\"e/\"")
+        .map(|(_, w)| w)
+        .collect();
+    assert_eq!(
+        vec![
+            Word::Variable("a".to_string()),
+            Word::PipeFile,
+            Word::ParenthesisOpen,
+            Word::Variable("b".to_string()),
+            Word::PipeFile,
+            Word::String("c".to_string()),
+            Word::ParenthesisClose,
+            Word::PipeFile,
+            Word::ParenthesisOpen,
+            Word::Variable("d".to_string()),
+            Word::ParenthesisClose,
+            Word::Slash,
+            Word::File("e/".to_string()),
+        ],
+        words
+    );
+
+    let words: Vec<Word> = Lexer::with_version("a>>b", 1, Version::V0)
+        .map(|(_, w)| w)
+        .collect();
+    assert_eq!(
+        vec![
+            Word::Variable("a".to_string()),
+            Word::PipeDirectory,
+            Word::PipeDirectory,
+            Word::Variable("b".to_string()),
+        ],
+        words
+    );
+    let mut l = Lexer::with_version("a/\"b/\"", 1, Version::V0);
+    assert_eq!(None, l.next());
+    assert_eq!(
+        Err(super::ConfigurationError::Lexer(
+            Position { line: 1, column: 3 },
+            LexerError::UnknowChar('/')
+        )),
+        l.err()
+    );
+}
+
+#[test]
+fn test_lexer_escape() {
+    let mut l = Lexer::new("\"a\"\n$\"b\\n \\u{d800}\"");
+    assert_eq!(
+        Some((Position { line: 1, column: 1 }, Word::File("a".to_string()))),
+        l.next()
+    );
+    assert_eq!(
+        Some((Position { line: 1, column: 4 }, Word::NewLine)),
+        l.next()
+    );
+    assert_eq!(None, l.next());
+    assert_eq!(
+        Err(super::ConfigurationError::Lexer(
+            Position { line: 2, column: 7 },
+            LexerError::InvalidUnicode("d800".to_string())
+        )),
+        l.err()
+    );
 }
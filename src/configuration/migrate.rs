@@ -0,0 +1,132 @@
+//! Rewrite the configuration files to the newest version of the grammar.
+
+use super::{
+    cst::{Cst, NodeKind, TokenKind},
+    lexer::Word,
+    version::Version,
+    ConfigurationError,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Rewrite the configuration file to [`Version::LATEST`], one version after
+/// the other. The comments and the layout are kept. A file already at the
+/// newest version is not changed.
+pub fn migrate(config: &str) -> Result<String, ConfigurationError> {
+    let mut config = config.to_string();
+    loop {
+        let cst = Cst::parse(&config)?;
+        config = match cst.version() {
+            Version::V0 => rewrite(&cst, Version::V1)?,
+            Version::V1 => return Ok(config),
+        };
+    }
+}
+
+/// Get the source with the header of the version `to`. The variables named
+/// like a keyword of `to` get a number suffix. The backslashes of CAGE-BUILD-0
+/// are separators in the paths and escaped in the strings, the other tokens
+/// are kept. A tag
+/// named like a keyword is an error, it is given by the user and can not be
+/// renamed here.
+fn rewrite(cst: &Cst, to: Version) -> Result<String, ConfigurationError> {
+    let tokens = cst.root().tokens();
+    let variables: HashSet<&str> = tokens
+        .iter()
+        .filter_map(|t| match &t.kind {
+            TokenKind::Word(Word::Variable(v)) => Some(v.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut renamed: HashMap<&str, String> = HashMap::new();
+    for v in variables.iter().filter(|v| to.keywords().contains(v)) {
+        let new = (1..)
+            .map(|i| format!("{}{}", v, i))
+            .find(|n| !variables.contains(&n[..]) && !renamed.values().any(|r| r == n))
+            .unwrap();
+        renamed.insert(v, new);
+    }
+    for tag in cst.root().nodes().filter(|n| n.kind == NodeKind::Tag) {
+        for (t, w) in tag.words() {
+            match w {
+                Word::Variable(v) if renamed.contains_key(v.as_str()) => {
+                    return Err(ConfigurationError::TagKeyword(t.position, v.clone()))
+                }
+                _ => {}
+            }
+        }
+    }
+    let header = Version::span(cst.source()).ok_or(ConfigurationError::VersionNotFound)?;
+
+    let mut out = String::with_capacity(cst.source().len());
+    for t in tokens {
+        let text = cst.text(t);
+        let new = match &t.kind {
+            TokenKind::Word(Word::Variable(v)) => renamed.get(v.as_str()),
+            _ => None,
+        };
+        if let Some(new) = new {
+            out.push_str(new);
+        } else if cst.version() == Version::V0 && matches!(t.kind, TokenKind::Word(Word::File(_))) {
+            out.push_str(&text.replace('\\', "/"));
+        } else if cst.version() == Version::V0 && matches!(t.kind, TokenKind::Word(Word::String(_)))
+        {
+            out.push_str(&text.replace('\\', "\\\\"));
+        } else if t.kind == TokenKind::Header {
+            out.push_str(&cst.source()[t.span.start..header.start]);
+            out.push_str(to.header());
+            out.push_str(&cst.source()[header.end..t.span.end]);
+        } else {
+            out.push_str(text);
+        }
+    }
+    Ok(out)
+}
+
+#[test]
+fn test_migrate() {
+    let config =
+        "#!/usr/bin/env cage\n# CAGE-BUILD-0 in a comment\nCAGE-BUILD-0 # header\nfile a \"a\"\n";
+    let migrated =
+        "#!/usr/bin/env cage\n# CAGE-BUILD-0 in a comment\nCAGE-BUILD-1 # header\nfile a \"a\"\n";
+    assert_eq!(Ok(migrated.to_string()), migrate(config));
+    assert_eq!(Ok(migrated.to_string()), migrate(migrated));
+    assert_eq!(
+        Ok("CAGE-BUILD-1\nfile secret2 \"a\"\ntag secret1\ndir $pkg secret2\n".to_string()),
+        migrate("CAGE-BUILD-0\nfile secret \"a\"\ntag secret1\ndir $pkg secret\n")
+    );
+    assert_eq!(
+        Ok("CAGE-BUILD-1\nfile a \"src/main.rs\" | $\"a\\\\q\" # a\\b\n".to_string()),
+        migrate("CAGE-BUILD-0\nfile a \"src\\main.rs\" | $\"a\\q\" # a\\b\n")
+    );
+    assert_eq!(
+        Ok("CAGE-BUILD-1 # was CAGE-BUILD-0\n".to_string()),
+        migrate("CAGE-BUILD-0 # was CAGE-BUILD-0\n")
+    );
+    assert_eq!(
+        Err(ConfigurationError::TagKeyword(
+            super::Position { line: 2, column: 5 },
+            "secret".to_string()
+        )),
+        migrate("CAGE-BUILD-0\ntag secret\nfile $pkg \"a\"\n")
+    );
+    assert_eq!(
+        Ok("CAGE-BUILD-1\ndir override1 \"a/\"\ndir $pkg [override1]\n".to_string()),
+        migrate("CAGE-BUILD-0\ndir override \"a/\"\ndir $pkg [override]\n")
+    );
+    assert_eq!(
+        Err(ConfigurationError::Lexer(
+            super::Position {
+                line: 2,
+                column: 13
+            },
+            super::lexer::LexerError::UnknowChar('/')
+        )),
+        migrate("CAGE-BUILD-0\ndir a \"a/\" / \"b/\"\n")
+    );
+    assert_eq!(
+        Err(ConfigurationError::VersionUnknown(
+            "CAGE-BUILD-9".to_string()
+        )),
+        migrate("CAGE-BUILD-9\n")
+    );
+}
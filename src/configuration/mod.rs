@@ -1,16 +1,30 @@
 #[allow(dead_code)]
+pub mod cst;
+#[allow(dead_code)]
 mod error;
 #[allow(dead_code)]
+mod format;
+#[allow(dead_code)]
+pub mod index;
+#[allow(dead_code)]
+pub mod language;
+#[allow(dead_code)]
 mod lexer;
 #[allow(dead_code)]
+mod migrate;
+#[allow(dead_code)]
 mod tree;
 #[allow(dead_code)]
 mod version;
 
-#[allow(unused_imports)]
-use version::Version;
+pub use version::Version;
 
 pub use error::ConfigurationError;
+pub use format::format;
+pub use migrate::migrate;
+#[allow(unused_imports)]
+pub use tree::Tree;
+pub use tree::{BuildError, Cache, Directory, Output, Outputs};
 
 /// The position of one object in the configuration file.
 #[derive(Debug, Copy, Clone, std::cmp::PartialEq)]
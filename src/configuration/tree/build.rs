@@ -0,0 +1,524 @@
+//! Build the definitions into virtual files and directories, from the
+//! repository files. Running the generators is not implemented, so a pipe is
+//! built only from the cache, else it is an error. A map pipe `*|` looks up
+//! the cache for each file, so only the modified files miss.
+
+use super::super::Position;
+use super::cache::{Cache, Key};
+use super::each::{each, entry_key};
+use super::{
+    compose, select, CompositionError, Directory, Generator, Object, ObjectValue, Pipe, Tree,
+};
+use crate::walk;
+use std::collections::HashMap;
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// The output of a definition.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Output {
+    File(Vec<u8>),
+    Directory(Directory),
+}
+
+/// The outputs of the built definitions, by name.
+pub type Outputs = HashMap<String, Output>;
+
+/// An error when building a definition.
+#[derive(Debug)]
+pub enum BuildError {
+    /// The target is not defined.
+    UndefinedTarget(String),
+    /// A repository file or directory can not be read.
+    Io(String, io::Error),
+    /// Two directories of a composition give the same path.
+    Composition(CompositionError),
+    /// The selected sub path is not in the directory.
+    NotFound(Position, String),
+    /// A pipe not in the cache, running the generators is not implemented.
+    Generator(Position),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UndefinedTarget(t) => write!(f, "The target {:?} is not defined", t),
+            Self::Io(path, _) => write!(f, "Can not read the repository path {:?}", path),
+            Self::Composition(e) => write!(f, "{}", e),
+            Self::NotFound(Position { line, column }, path) => write!(
+                f,
+                "The path {:?} selected at line {} column {} is not in the directory",
+                path, line, column
+            ),
+            Self::Generator(Position { line, column }) => write!(
+                f,
+                "The pipe at line {} column {} needs a generator, running the generators is not implemented",
+                line, column
+            ),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(_, e) => Some(e),
+            Self::Composition(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Output {
+    /// Write the output at `path`. A directory is written with its files into
+    /// the directory `path`, the other files of this directory are kept.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        match self {
+            Output::File(content) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, content)
+            }
+            Output::Directory(directory) => {
+                fs::create_dir_all(path)?;
+                for (p, content) in directory.iter() {
+                    let file = path.join(p);
+                    if let Some(parent) = file.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(file, content)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Tree {
+    /// Build the definitions needed by `target`, with the repository `root`.
+    /// The definitions already in `outputs` are not built again, the outputs
+    /// of the pipes are taken from `cache`.
+    pub fn build(
+        &self,
+        root: &Path,
+        target: &str,
+        cache: &Cache,
+        outputs: &mut Outputs,
+    ) -> Result<(), BuildError> {
+        let plan = self
+            .plan(target)
+            .ok_or_else(|| BuildError::UndefinedTarget(target.to_string()))?;
+        for d in self.definitions.iter() {
+            let name = d.key.name();
+            if plan.contains(&name) && !outputs.contains_key(name) {
+                let output = d.value.build(root, cache, outputs)?;
+                outputs.insert(name.to_string(), output);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Object {
+    fn build(&self, root: &Path, cache: &Cache, outputs: &Outputs) -> Result<Output, BuildError> {
+        Ok(match &self.value {
+            ObjectValue::File(path) if path.is_empty() || path.ends_with('/') => {
+                Output::Directory(read_dir(root, path)?)
+            }
+            ObjectValue::File(path) => Output::File(read_file(root, path)?),
+            ObjectValue::Glob(g) => Output::Directory(
+                g.select(root)
+                    .map_err(|e| BuildError::Io(g.base().to_string(), e))?,
+            ),
+            ObjectValue::Literal(s) => Output::File(s.as_bytes().to_vec()),
+            ObjectValue::Variable(v) => outputs[v].clone(),
+            ObjectValue::Aggregation(entries) => {
+                let mut directory = Directory::new();
+                for (key, o) in entries.iter() {
+                    let key = key.trim_end_matches('/');
+                    match o.build(root, cache, outputs)? {
+                        Output::File(content) => {
+                            directory.insert(key.to_string(), content);
+                        }
+                        Output::Directory(d) => directory.extend(
+                            d.into_iter()
+                                .map(|(p, content)| (format!("{}/{}", key, p), content)),
+                        ),
+                    }
+                }
+                Output::Directory(directory)
+            }
+            ObjectValue::Composition(list, conflict) => {
+                let mut directories = Vec::with_capacity(list.len());
+                for o in list.iter() {
+                    directories.push((o.position, o.build_directory(root, cache, outputs)?));
+                }
+                Output::Directory(compose(directories, *conflict).map_err(BuildError::Composition)?)
+            }
+            ObjectValue::Select(o, path) => {
+                let directory = o.build_directory(root, cache, outputs)?;
+                select(&directory, path)
+                    .ok_or_else(|| BuildError::NotFound(self.position, path.clone()))?
+            }
+            ObjectValue::Pipe(p) if p.each => {
+                let key = p.key(root, cache, outputs)?;
+                let input = p.input.build_directory(root, cache, outputs)?;
+                Output::Directory(each(input, |path, content| {
+                    let mut key = key.clone();
+                    key.part(b"entry");
+                    key.part(&entry_key(path, &content));
+                    match cache.get(&key.finish()) {
+                        Some(Output::File(c)) => Ok(c.clone()),
+                        _ => Err(BuildError::Generator(self.position)),
+                    }
+                })?)
+            }
+            ObjectValue::Pipe(p) => {
+                let mut key = p.key(root, cache, outputs)?;
+                p.input.key_input(&mut key, root, cache, outputs)?;
+                cache
+                    .get(&key.finish())
+                    .cloned()
+                    .ok_or(BuildError::Generator(self.position))?
+            }
+            ObjectValue::Inputs(_) => unreachable!("The inputs are only the input of a pipe"),
+        })
+    }
+
+    /// Add the input of a pipe to its key, the named inputs one by one.
+    fn key_input(
+        &self,
+        key: &mut Key,
+        root: &Path,
+        cache: &Cache,
+        outputs: &Outputs,
+    ) -> Result<(), BuildError> {
+        match &self.value {
+            ObjectValue::Inputs(inputs) => {
+                key.part(b"inputs");
+                for (name, o) in inputs.iter() {
+                    key.part(name.as_bytes());
+                    key.output(&o.build(root, cache, outputs)?);
+                }
+            }
+            _ => key.output(&self.build(root, cache, outputs)?),
+        }
+        Ok(())
+    }
+
+    /// Build an object that is a directory, the kinds are checked by the parser.
+    fn build_directory(
+        &self,
+        root: &Path,
+        cache: &Cache,
+        outputs: &Outputs,
+    ) -> Result<Directory, BuildError> {
+        match self.build(root, cache, outputs)? {
+            Output::Directory(d) => Ok(d),
+            Output::File(_) => unreachable!("The kinds are checked by the parser"),
+        }
+    }
+}
+
+impl Pipe {
+    /// The key of the call without its input: the generator, the operator, all
+    /// the declared options, sorted by name, and the active tags. A generator
+    /// from the repository or from a variable is the content of its module.
+    fn key(&self, root: &Path, cache: &Cache, outputs: &Outputs) -> Result<Key, BuildError> {
+        let mut key = Key::new();
+        match &self.generator {
+            Generator::Url(url) | Generator::Default(super::GeneratorDefault { url, .. }) => {
+                key.part(b"url");
+                key.part(url.as_bytes());
+            }
+            Generator::Path(path) => {
+                key.part(b"module");
+                key.part(&read_file(root, path)?);
+            }
+            Generator::Variable(v) => {
+                key.part(b"module");
+                key.output(&outputs[v]);
+            }
+            Generator::Pipeline(o) => {
+                key.part(b"module");
+                key.output(&o.build(root, cache, outputs)?);
+            }
+        }
+        let flags = [
+            self.input_is_dir.map_or(0, |d| 1 + d as u8),
+            self.output_is_dir as u8,
+            self.each as u8,
+        ];
+        key.part(&flags);
+        let mut options: Vec<&(String, String)> = self.options.iter().collect();
+        options.sort();
+        key.part(&(options.len() as u64).to_le_bytes());
+        for (name, value) in options {
+            key.part(name.as_bytes());
+            key.part(value.as_bytes());
+        }
+        let tags: Vec<&str> = cache.tags().collect();
+        key.part(&(tags.len() as u64).to_le_bytes());
+        for tag in tags {
+            key.part(tag.as_bytes());
+        }
+        Ok(key)
+    }
+}
+
+/// Read the repository file `path`, a link out of the repository is an error.
+fn read_file(root: &Path, path: &str) -> Result<Vec<u8>, BuildError> {
+    let error = |e| BuildError::Io(path.to_string(), e);
+    walk::files(root, path, |_| false).map_err(error)?;
+    fs::read(root.join(path)).map_err(error)
+}
+
+/// Read the files of the repository directory `path`, empty or ending with `/`.
+/// The links follow the policy of [`walk::files`].
+fn read_dir(root: &Path, path: &str) -> Result<Directory, BuildError> {
+    let mut directory = Directory::new();
+    let files =
+        walk::files(root, path, |_| false).map_err(|e| BuildError::Io(path.to_string(), e))?;
+    for p in files {
+        let content = fs::read(root.join(&p)).map_err(|e| BuildError::Io(p.clone(), e))?;
+        directory.insert(p[path.len()..].to_string(), content);
+    }
+    Ok(directory)
+}
+
+#[test]
+fn test_build() {
+    let dir = crate::temp::TempDir::new("build");
+    for (path, content) in [
+        ("front/index.html", "index"),
+        ("front/lib/app.js", "app"),
+        ("style.css", "body {}"),
+        ("doc/a.md", "a"),
+        ("doc/b.txt", "b"),
+    ]
+    .iter()
+    {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    }
+
+    let tree = Tree::parse(
+        r#"CAGE-BUILD-1
+dir front "front/"
+dir site {"css/main.css": "style.css", "VERSION": $"1.0", "doc/": "doc/*.md"}
+dir $pkg override [front, site, { "index.html": $"new" }]
+dir $run front / "lib/"
+file $test front / "index.html"
+dir min front > $"https://exemple.com/min"
+dir missing site / "none/"
+"#,
+    )
+    .unwrap();
+
+    let mut outputs = Outputs::new();
+    tree.build(&dir, "$pkg", &Cache::new(), &mut outputs)
+        .unwrap();
+    let files = |o: &Output| match o {
+        Output::Directory(d) => d
+            .iter()
+            .map(|(p, c)| format!("{}={}", p, String::from_utf8_lossy(c)))
+            .collect::<Vec<_>>(),
+        Output::File(_) => panic!("Expected a directory"),
+    };
+    assert_eq!(
+        vec![
+            "VERSION=1.0",
+            "css/main.css=body {}",
+            "doc/a.md=a",
+            "index.html=new",
+            "lib/app.js=app",
+        ],
+        files(&outputs["$pkg"])
+    );
+    assert_eq!(3, outputs.len());
+
+    tree.build(&dir, "$run", &Cache::new(), &mut outputs)
+        .unwrap();
+    assert_eq!(vec!["app.js=app"], files(&outputs["$run"]));
+    tree.build(&dir, "$test", &Cache::new(), &mut outputs)
+        .unwrap();
+    assert_eq!(Output::File(b"index".to_vec()), outputs["$test"]);
+
+    assert!(matches!(
+        tree.build(&dir, "min", &Cache::new(), &mut outputs),
+        Err(BuildError::Generator(Position {
+            line: 7,
+            column: 15
+        }))
+    ));
+    assert!(matches!(
+        tree.build(&dir, "missing", &Cache::new(), &mut outputs),
+        Err(BuildError::NotFound(_, p)) if p == "none/"
+    ));
+    assert!(matches!(
+        tree.build(&dir, "other", &Cache::new(), &mut outputs),
+        Err(BuildError::UndefinedTarget(_))
+    ));
+    assert!(matches!(
+        Tree::parse("CAGE-BUILD-1\ndir a [\"front/\", \"front/\"]\n")
+            .unwrap()
+            .build(&dir, "a", &Cache::new(), &mut Outputs::new()),
+        Err(BuildError::Composition(_))
+    ));
+
+    let out = dir.join("out");
+    outputs["$run"].write(&out).unwrap();
+    outputs["$test"].write(&out.join("sub/index.html")).unwrap();
+    assert_eq!("app", fs::read_to_string(out.join("app.js")).unwrap());
+    assert_eq!(
+        "index",
+        fs::read_to_string(out.join("sub/index.html")).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_build_link() {
+    use std::os::unix::fs::symlink;
+
+    let dir = crate::temp::TempDir::new("build-link");
+    let root = dir.join("repo");
+    fs::create_dir_all(root.join("front/sub")).unwrap();
+    fs::write(root.join("front/sub/app.js"), "app").unwrap();
+    fs::write(dir.join("outside"), "outside").unwrap();
+    symlink("sub", root.join("front/alias")).unwrap();
+    symlink("..", root.join("front/loop")).unwrap();
+
+    let tree =
+        Tree::parse("CAGE-BUILD-1\ndir $pkg \"front/\"\nfile host \"front/host\"\n").unwrap();
+    let mut outputs = Outputs::new();
+    tree.build(&root, "$pkg", &Cache::new(), &mut outputs)
+        .unwrap();
+    match &outputs["$pkg"] {
+        Output::Directory(d) => assert_eq!(
+            vec!["alias/app.js", "sub/app.js"],
+            d.keys().collect::<Vec<_>>()
+        ),
+        Output::File(_) => panic!("Expected a directory"),
+    }
+
+    symlink("../../outside", root.join("front/host")).unwrap();
+    assert!(matches!(
+        tree.build(&root, "host", &Cache::new(), &mut Outputs::new()),
+        Err(BuildError::Io(p, _)) if p == "front/host"
+    ));
+    assert!(matches!(
+        tree.build(&root, "$pkg", &Cache::new(), &mut Outputs::new()),
+        Err(BuildError::Io(p, _)) if p == "front/"
+    ));
+}
+
+#[test]
+fn test_build_cache() {
+    let dir = crate::temp::TempDir::new("build-cache");
+    fs::write(dir.join("a.c"), "int a;").unwrap();
+    let config = |options: &str| {
+        Tree::parse(&format!(
+            "CAGE-BUILD-1\nfile a \"a.c\" | $\"https://exemple.com/cc\" {}\n",
+            options
+        ))
+        .unwrap()
+    };
+    let key = |tree: &Tree| match &tree.definitions[0].value.value {
+        ObjectValue::Pipe(p) => {
+            let mut key = p.key(&dir, &Cache::new(), &Outputs::new()).unwrap();
+            p.input
+                .key_input(&mut key, &dir, &Cache::new(), &Outputs::new())
+                .unwrap();
+            key.finish()
+        }
+        v => panic!("Unexpected value {:?}", v),
+    };
+
+    let tree = config("{\"level\": $\"3\", \"debug\": $\"no\"}");
+    let mut cache = Cache::new();
+    cache.insert(key(&tree), Output::File(b"a.o".to_vec()));
+    let mut outputs = Outputs::new();
+    tree.build(&dir, "a", &cache, &mut outputs).unwrap();
+    assert_eq!(Output::File(b"a.o".to_vec()), outputs["a"]);
+
+    // The options are sorted, a changed option or input is an other call.
+    let sorted = config("{\"debug\": $\"no\", \"level\": $\"3\"}");
+    assert!(sorted.build(&dir, "a", &cache, &mut Outputs::new()).is_ok());
+    for other in [
+        config("{\"level\": $\"2\", \"debug\": $\"no\"}"),
+        config("{\"level\": $\"3\"}"),
+        config(""),
+    ]
+    .iter()
+    {
+        assert!(matches!(
+            other.build(&dir, "a", &cache, &mut Outputs::new()),
+            Err(BuildError::Generator(_))
+        ));
+    }
+    assert!(matches!(
+        tree.build(
+            &dir,
+            "a",
+            &Cache::with_tags(vec!["release"]),
+            &mut Outputs::new()
+        ),
+        Err(BuildError::Generator(_))
+    ));
+    fs::write(dir.join("a.c"), "int b;").unwrap();
+    assert!(matches!(
+        tree.build(&dir, "a", &cache, &mut Outputs::new()),
+        Err(BuildError::Generator(_))
+    ));
+}
+
+#[test]
+fn test_build_each() {
+    let dir = crate::temp::TempDir::new("build-each");
+    fs::create_dir_all(dir.join("js")).unwrap();
+    fs::write(dir.join("js/a.js"), "var  a;").unwrap();
+    fs::write(dir.join("js/b.js"), "var  b;").unwrap();
+    let tree =
+        Tree::parse("CAGE-BUILD-1\ndir min \"js/\" *| $\"https://exemple.com/min\"\n").unwrap();
+    let base = match &tree.definitions[0].value.value {
+        ObjectValue::Pipe(p) => p.key(&dir, &Cache::new(), &Outputs::new()).unwrap(),
+        v => panic!("Unexpected value {:?}", v),
+    };
+    let mut cache = Cache::new();
+    for (path, content, output) in
+        [("a.js", "var  a;", "var a;"), ("b.js", "var  b;", "var b;")].iter()
+    {
+        let mut key = base.clone();
+        key.part(b"entry");
+        key.part(&entry_key(path, content.as_bytes()));
+        cache.insert(key.finish(), Output::File(output.as_bytes().to_vec()));
+    }
+
+    let mut outputs = Outputs::new();
+    tree.build(&dir, "min", &cache, &mut outputs).unwrap();
+    assert_eq!(
+        Output::Directory(
+            vec![
+                ("a.js".to_string(), b"var a;".to_vec()),
+                ("b.js".to_string(), b"var b;".to_vec())
+            ]
+            .into_iter()
+            .collect()
+        ),
+        outputs["min"]
+    );
+
+    // A modified file misses the cache, the others keep their entry.
+    fs::write(dir.join("js/b.js"), "var  c;").unwrap();
+    assert!(matches!(
+        tree.build(&dir, "min", &cache, &mut Outputs::new()),
+        Err(BuildError::Generator(_))
+    ));
+    fs::write(dir.join("js/b.js"), "var  b;").unwrap();
+    fs::write(dir.join("js/c.js"), "var  c;").unwrap();
+    assert!(tree
+        .build(&dir, "min", &cache, &mut Outputs::new())
+        .is_err());
+}
@@ -0,0 +1,122 @@
+//! The cache of the generator outputs, by the key of the call. Running the
+//! generators is not implemented, so a pipe is built only from this cache.
+
+use super::build::Output;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// The domain of the keys, change it changes all keys.
+const DOMAIN: &[u8] = b"cage-cache-0";
+
+/// The outputs of the generator calls, by key, for the build with the active
+/// tags. The tags are in the key of each call.
+#[derive(Debug, Default)]
+pub struct Cache {
+    /// The active tags, sorted.
+    tags: Vec<String>,
+    outputs: HashMap<[u8; 32], Output>,
+}
+
+/// The key of a generator call, built part after part. Each part is prefixed by
+/// its length, so two different lists of parts never give the same key.
+#[derive(Clone)]
+pub struct Key(Sha256);
+
+impl Cache {
+    /// Create the cache of a build without active tag.
+    pub fn new() -> Self {
+        Cache::default()
+    }
+
+    /// Create the cache of a build with the active tags, like with `--tag release`.
+    pub fn with_tags<'a, T: IntoIterator<Item = &'a str>>(tags: T) -> Self {
+        let mut tags: Vec<String> = tags.into_iter().map(String::from).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        Cache {
+            tags,
+            outputs: HashMap::new(),
+        }
+    }
+
+    /// The active tags, sorted.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    /// Get the output of the call, None if it is not in the cache.
+    pub fn get(&self, key: &[u8; 32]) -> Option<&Output> {
+        self.outputs.get(key)
+    }
+
+    /// Add the output of the call.
+    pub fn insert(&mut self, key: [u8; 32], output: Output) {
+        self.outputs.insert(key, output);
+    }
+}
+
+impl Key {
+    pub fn new() -> Self {
+        let mut key = Key(Sha256::new());
+        key.part(DOMAIN);
+        key
+    }
+
+    /// Add a part to the key.
+    pub fn part(&mut self, part: &[u8]) {
+        self.0.update((part.len() as u64).to_le_bytes());
+        self.0.update(part);
+    }
+
+    /// Add a file or a directory, with its paths.
+    pub fn output(&mut self, output: &Output) {
+        match output {
+            Output::File(content) => {
+                self.part(b"file");
+                self.part(content);
+            }
+            Output::Directory(d) => {
+                self.part(b"dir");
+                self.part(&(d.len() as u64).to_le_bytes());
+                for (path, content) in d.iter() {
+                    self.part(path.as_bytes());
+                    self.part(content);
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+#[test]
+fn test_key() {
+    let key = |parts: &[&[u8]]| {
+        let mut k = Key::new();
+        for p in parts {
+            k.part(p);
+        }
+        k.finish()
+    };
+    assert_eq!(key(&[b"a", b"b"]), key(&[b"a", b"b"]));
+    assert_ne!(key(&[b"a", b"b"]), key(&[b"ab"]));
+    assert_ne!(key(&[b"a", b""]), key(&[b"a"]));
+
+    let output = |o: &Output| {
+        let mut k = Key::new();
+        k.output(o);
+        k.finish()
+    };
+    let dir = |files: &[(&str, &str)]| {
+        Output::Directory(
+            files
+                .iter()
+                .map(|(p, c)| (p.to_string(), c.as_bytes().to_vec()))
+                .collect(),
+        )
+    };
+    assert_ne!(output(&Output::File(Vec::new())), output(&dir(&[])));
+    assert_ne!(output(&dir(&[("a", "b")])), output(&dir(&[("b", "a")])));
+}
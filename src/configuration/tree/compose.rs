@@ -0,0 +1,149 @@
+//! Materialize a composition `[]`: concatenate several virtual directories.
+
+use super::super::Position;
+use std::collections::BTreeMap;
+use std::{error::Error, fmt};
+
+/// What to do when several directories of a composition give the same path.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Conflict {
+    /// The default, the composition fails with the positions of both directories.
+    Error,
+    /// With `override [...]`, the last directory wins.
+    Override,
+}
+
+/// A virtual directory, the file contents by path relative to the directory.
+pub type Directory = BTreeMap<String, Vec<u8>>;
+
+/// Two directories of a composition give the same path, or one gives a file
+/// where the other gives a directory.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CompositionError {
+    pub path: String,
+    /// The position of the first directory that gives the path.
+    pub first: Position,
+    /// The position of the second directory.
+    pub second: Position,
+}
+
+impl fmt::Display for CompositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The path {:?} is given by the directory at line {} column {} and by the directory at line {} column {}, use `override [...]` to keep the last",
+            self.path, self.first.line, self.first.column, self.second.line, self.second.column
+        )
+    }
+}
+impl Error for CompositionError {}
+
+/// Concatenate the directories, with the position of their object in the
+/// configuration file.
+pub fn compose<I>(directories: I, conflict: Conflict) -> Result<Directory, CompositionError>
+where
+    I: IntoIterator<Item = (Position, Directory)>,
+{
+    let mut out = Directory::new();
+    // The position of the directory that gives each path of out.
+    let mut origins: BTreeMap<String, Position> = BTreeMap::new();
+    for (position, directory) in directories {
+        for (path, content) in directory {
+            for other in conflicts(&origins, &path) {
+                match conflict {
+                    Conflict::Error => {
+                        return Err(CompositionError {
+                            path,
+                            first: origins[&other],
+                            second: position,
+                        })
+                    }
+                    Conflict::Override => {
+                        out.remove(&other);
+                        origins.remove(&other);
+                    }
+                }
+            }
+            out.insert(path.clone(), content);
+            origins.insert(path, position);
+        }
+    }
+    Ok(out)
+}
+
+/// The paths already given that conflict with `path`: the same path, a parent
+/// used as a file, or files inside `path` used as a directory.
+fn conflicts(origins: &BTreeMap<String, Position>, path: &str) -> Vec<String> {
+    let parents = path
+        .match_indices('/')
+        .map(|(i, _)| &path[..i])
+        .chain(std::iter::once(path))
+        .filter(|p| origins.contains_key(*p))
+        .map(String::from);
+    let prefix = format!("{}/", path);
+    let children = origins
+        .range(prefix.clone()..)
+        .map(|(p, _)| p)
+        .take_while(|p| p.starts_with(&prefix))
+        .cloned();
+    parents.chain(children).collect()
+}
+
+#[test]
+fn test_compose() {
+    let p = |line, column| Position { line, column };
+    let dir = |files: &[&str]| -> Directory {
+        files
+            .iter()
+            .map(|f| (f.to_string(), f.as_bytes().to_vec()))
+            .collect()
+    };
+
+    assert_eq!(
+        Ok(dir(&["a", "b/c", "b/d"])),
+        compose(
+            vec![(p(1, 1), dir(&["a", "b/c"])), (p(1, 5), dir(&["b/d"]))],
+            Conflict::Error
+        )
+    );
+    assert_eq!(
+        Err(CompositionError {
+            path: "b/c".to_string(),
+            first: p(1, 1),
+            second: p(2, 3),
+        }),
+        compose(
+            vec![
+                (p(1, 1), dir(&["a", "b/c"])),
+                (p(1, 9), dir(&["d"])),
+                (p(2, 3), dir(&["b/c"]))
+            ],
+            Conflict::Error
+        )
+    );
+    assert_eq!(
+        Err(CompositionError {
+            path: "b".to_string(),
+            first: p(1, 1),
+            second: p(1, 9),
+        }),
+        compose(
+            vec![(p(1, 1), dir(&["b/c"])), (p(1, 9), dir(&["b"]))],
+            Conflict::Error
+        )
+    );
+
+    let mut last = dir(&["a"]);
+    last.insert("b/c".to_string(), b"last".to_vec());
+    assert_eq!(
+        Ok(last),
+        compose(
+            vec![
+                (p(1, 1), dir(&["a/x", "a/y", "b/c"])),
+                (p(1, 9), dir(&["a"])),
+                (p(2, 3), [("b/c".to_string(), b"last".to_vec())].into()),
+            ],
+            Conflict::Override
+        )
+    );
+}
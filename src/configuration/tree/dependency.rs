@@ -0,0 +1,221 @@
+use super::{Definition, Generator, Glob, ObjectValue, Pipe, Tree, IGNORE_FILE};
+use crate::walk;
+use std::collections::{BTreeSet, HashSet};
+use std::{io, path::Path};
+
+impl Definition {
+    /// Get the repository files and directories read by the definition, the
+    /// globs and the used variables.
+    fn inputs(&self) -> (Vec<&str>, Vec<&Glob>, Vec<&str>) {
+        let mut files = Vec::new();
+        let mut globs = Vec::new();
+        let mut variables = Vec::new();
+        self.value.walk(|o| match &o.value {
+            ObjectValue::File(f) => files.push(f.as_str()),
+            ObjectValue::Glob(g) => globs.push(g),
+            ObjectValue::Variable(v) => variables.push(v.as_str()),
+            ObjectValue::Pipe(Pipe { generator, .. }) => match generator {
+                Generator::Path(f) => files.push(f.as_str()),
+                Generator::Variable(v) => variables.push(v.as_str()),
+                Generator::Url(_) | Generator::Default(_) | Generator::Pipeline(_) => {}
+            },
+            _ => {}
+        });
+        (files, globs, variables)
+    }
+}
+
+impl Tree {
+    /// Get all repository files and directories read by the configuration, a
+    /// glob gives its base directory and the [`IGNORE_FILE`].
+    pub fn watched_files(&self) -> HashSet<&str> {
+        self.definitions
+            .iter()
+            .flat_map(|d| {
+                let (files, globs, _) = d.inputs();
+                let ignore = globs.first().map(|_| IGNORE_FILE);
+                files
+                    .into_iter()
+                    .chain(globs.into_iter().map(Glob::base))
+                    .chain(ignore)
+            })
+            .collect()
+    }
+
+    /// List the repository files read by the build, sorted: the files of the
+    /// file and directory literals, the files selected by the globs, and the
+    /// [`IGNORE_FILE`] if there is a glob. A missing path is skipped, the links
+    /// follow the policy of [`walk::files`].
+    pub fn read_files(&self, root: &Path) -> io::Result<BTreeSet<String>> {
+        let mut read = BTreeSet::new();
+        for d in self.definitions.iter() {
+            let (files, globs, _) = d.inputs();
+            for f in files {
+                match walk::files(root, f, |_| false) {
+                    Ok(list) => read.extend(list),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            for g in globs.iter() {
+                read.extend(g.files(root)?);
+            }
+            if !globs.is_empty() && root.join(IGNORE_FILE).is_file() {
+                read.insert(IGNORE_FILE.to_string());
+            }
+        }
+        Ok(read)
+    }
+
+    /// Get the name of the definitions to rebuild when the repository files
+    /// `changed` are modified, created or removed, in the definition order.
+    pub fn invalidated(&self, changed: &[&str]) -> Vec<&str> {
+        let mut invalid: Vec<&str> = Vec::new();
+        for d in self.definitions.iter() {
+            let (files, globs, variables) = d.inputs();
+            if files.iter().any(|f| changed.iter().any(|c| contains(f, c)))
+                || globs.iter().any(|g| changed.iter().any(|c| g.matches(c)))
+                || (!globs.is_empty() && changed.contains(&IGNORE_FILE))
+                || variables.iter().any(|v| invalid.contains(v))
+            {
+                invalid.push(d.key.name());
+            }
+        }
+        invalid
+    }
+
+    /// Get the name of the definitions to build for the definition `target`:
+    /// the target and its dependencies, in the definition order. None if the
+    /// target is not defined.
+    pub fn plan(&self, target: &str) -> Option<Vec<&str>> {
+        let mut needed: Vec<&str> = vec![target];
+        let mut found = false;
+        for d in self.definitions.iter().rev() {
+            if needed.contains(&d.key.name()) {
+                found |= d.key.name() == target;
+                needed.extend(d.inputs().2);
+            }
+        }
+        if !found {
+            return None;
+        }
+        Some(
+            self.definitions
+                .iter()
+                .map(|d| d.key.name())
+                .filter(|n| needed.contains(n))
+                .collect(),
+        )
+    }
+}
+
+/// The file or directory `input` contains the file `changed`.
+fn contains(input: &str, changed: &str) -> bool {
+    let input = input.trim_end_matches('/');
+    match changed.strip_prefix(input) {
+        Some(rest) => input.is_empty() || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[test]
+fn test_invalidated() {
+    let tree = Tree::parse(
+        r#"CAGE-BUILD-1
+file style "style.css" | $"https://exemple.com/minifier"
+dir front "front/" > "tools/bundler.wasm"
+dir web [ front, { "style.css": style } ]
+dir api "api/" > $"https://exemple.com/compiler"
+dir doc "doc/**/*.md;!doc/draft/**" > $"https://exemple.com/markdown"
+file tool "tools/gen/" >| $"https://exemple.com/compiler"
+dir gen { "out.txt": "in.txt" | (tool), "lib/": "libs/" / "gen/" }
+dir $pkg [ web, api, doc, gen ]
+"#,
+    )
+    .unwrap();
+
+    let mut watched: Vec<&str> = tree.watched_files().into_iter().collect();
+    watched.sort_unstable();
+    assert_eq!(
+        vec![
+            ".cageignore",
+            "api/",
+            "doc/",
+            "front/",
+            "in.txt",
+            "libs/gen/",
+            "style.css",
+            "tools/bundler.wasm",
+            "tools/gen/"
+        ],
+        watched
+    );
+
+    assert_eq!(
+        vec!["front", "web", "$pkg"],
+        tree.invalidated(&["front/app.js"])
+    );
+    assert_eq!(
+        vec!["front", "web", "$pkg"],
+        tree.invalidated(&["tools/bundler.wasm"])
+    );
+    assert_eq!(
+        vec!["style", "web", "$pkg"],
+        tree.invalidated(&["style.css"])
+    );
+    assert_eq!(vec!["api", "$pkg"], tree.invalidated(&["api/src/main.rs"]));
+    assert_eq!(Vec::<&str>::new(), tree.invalidated(&["frontend/app.js"]));
+    assert_eq!(Vec::<&str>::new(), tree.invalidated(&["README.md"]));
+    assert_eq!(vec!["doc", "$pkg"], tree.invalidated(&["doc/new/page.md"]));
+    assert_eq!(Vec::<&str>::new(), tree.invalidated(&["doc/draft/page.md"]));
+    assert_eq!(Vec::<&str>::new(), tree.invalidated(&["doc/image.png"]));
+    assert_eq!(
+        vec!["tool", "gen", "$pkg"],
+        tree.invalidated(&["tools/gen/main.rs"])
+    );
+    assert_eq!(vec!["gen", "$pkg"], tree.invalidated(&["libs/gen/a.rs"]));
+    assert_eq!(Vec::<&str>::new(), tree.invalidated(&["libs/other/a.rs"]));
+    assert_eq!(vec!["doc", "$pkg"], tree.invalidated(&[".cageignore"]));
+
+    assert_eq!(Some(vec!["style", "front", "web"]), tree.plan("web"));
+    assert_eq!(Some(vec!["api"]), tree.plan("api"));
+    assert_eq!(Some(vec!["tool", "gen"]), tree.plan("gen"));
+    assert_eq!(
+        Some(vec![
+            "style", "front", "web", "api", "doc", "tool", "gen", "$pkg"
+        ]),
+        tree.plan("$pkg")
+    );
+    assert_eq!(None, tree.plan("$run"));
+}
+
+#[test]
+fn test_invalidated_inputs() {
+    let tree = Tree::parse(
+        r#"CAGE-BUILD-1
+dir front "front/" > "tools/bundler.wasm"
+file tool "tools/gen/" >| $"https://exemple.com/compiler"
+file gen "in.txt" | (tool)
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(vec!["gen"], tree.invalidated(&["in.txt"]));
+    // Without glob, the ignore file is not read.
+    assert_eq!(Vec::<&str>::new(), tree.invalidated(&[".cageignore"]));
+}
+
+#[test]
+fn test_invalidated_select_variable() {
+    let tree = Tree::parse(
+        "CAGE-BUILD-1\ndir src \"src/\"\ndir all src\ndir lib all / \"lib/\"\nfile a all / \"a.c\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        vec!["src", "all", "lib"],
+        tree.invalidated(&["src/lib/x.c"])
+    );
+    assert_eq!(vec!["src", "all", "a"], tree.invalidated(&["src/a.c"]));
+    assert_eq!(vec!["src", "all"], tree.invalidated(&["src/other.c"]));
+    assert_eq!(Some(vec!["lib"]), tree.plan("lib"));
+}
@@ -0,0 +1,108 @@
+//! Materialize a map pipe `*|`: call the generator for each file of a directory.
+
+use super::Directory;
+use sha2::{Digest, Sha256};
+use std::thread;
+
+/// The cache key of one file of the input directory: SHA-256 of the path and
+/// the content, each prefixed by its length. A file not modified keeps its key,
+/// so only the modified files are generated again.
+pub fn entry_key(path: &str, content: &[u8]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    for part in [path.as_bytes(), content].iter() {
+        h.update((part.len() as u64).to_le_bytes());
+        h.update(part);
+    }
+    h.finalize().into()
+}
+
+/// Call `generator` with the path and the content of each file of the
+/// directory, in parallel. The output directory has the same layout. If some
+/// calls fail, the error of the first path is returned.
+pub fn each<F, E>(directory: Directory, generator: F) -> Result<Directory, E>
+where
+    F: Fn(&str, Vec<u8>) -> Result<Vec<u8>, E> + Sync,
+    E: Send,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let size = directory.len().div_ceil(threads).max(1);
+    let mut chunks: Vec<Vec<(String, Vec<u8>)>> = Vec::new();
+    for (i, entry) in directory.into_iter().enumerate() {
+        if i % size == 0 {
+            chunks.push(Vec::with_capacity(size));
+        }
+        chunks.last_mut().unwrap().push(entry);
+    }
+
+    let generator = &generator;
+    let results: Vec<(String, Result<Vec<u8>, E>)> = thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .into_iter()
+                        .map(|(path, content)| {
+                            let output = generator(&path, content);
+                            (path, output)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+
+    let mut output = Directory::new();
+    for (path, result) in results {
+        output.insert(path, result?);
+    }
+    Ok(output)
+}
+
+#[test]
+fn test_each() {
+    let input: Directory = (0..20)
+        .map(|i| {
+            (
+                format!("js/{:02}.js", i),
+                format!("var  x = {};", i).into_bytes(),
+            )
+        })
+        .collect();
+
+    let output = each(input.clone(), |_, content| -> Result<Vec<u8>, ()> {
+        Ok(String::from_utf8(content)
+            .unwrap()
+            .replace(' ', "")
+            .into_bytes())
+    })
+    .unwrap();
+    assert_eq!(
+        input.keys().collect::<Vec<_>>(),
+        output.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(Some(&b"varx=7;".to_vec()), output.get("js/07.js"));
+
+    assert_eq!(
+        Err("js/03.js".to_string()),
+        each(input.clone(), |path, content| {
+            if path.ends_with("3.js") {
+                Err(path.to_string())
+            } else {
+                Ok(content)
+            }
+        })
+    );
+    assert_eq!(
+        Ok(Directory::new()),
+        each(Directory::new(), |_, c| -> Result<_, ()> { Ok(c) })
+    );
+
+    assert_eq!(entry_key("a.js", b"x"), entry_key("a.js", b"x"));
+    assert_ne!(entry_key("a.js", b"x"), entry_key("b.js", b"x"));
+    assert_ne!(entry_key("a.js", b"x"), entry_key("a.js", b"y"));
+}
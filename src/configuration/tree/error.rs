@@ -0,0 +1,73 @@
+use super::super::lexer::Word;
+use std::{error::Error, fmt};
+
+/// A parser error.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParserError {
+    /// A token not expected here.
+    Unexpected(Word),
+    /// The end of the file is reached inside a definition.
+    UnexpectedEnd,
+    /// A variable, a system variable or a tag defined two times.
+    Redefined(String),
+    /// Two secrets given by the same environment variable, like `signKey` and
+    /// `SignKey`.
+    SameSecretVariable(String, String),
+    /// A variable used but not defined before.
+    Undefined(String),
+    /// A file path absolute or out of the repository.
+    InvalidPath(String),
+    /// A glob without including pattern.
+    InvalidGlob(String),
+    /// An option of a generator empty or given two times.
+    InvalidOption(String),
+    /// A named input empty, with a `/` or given two times.
+    InvalidInput(String),
+    /// An aggregation key empty, with `..` or a control char.
+    InvalidKey(String),
+    /// An aggregation key given two times.
+    DuplicateKey(String),
+    /// An aggregation key inside an other key of the same aggregation, like
+    /// `a/b` and `a`.
+    OverlappingKey(String, String),
+    /// A file is given where a directory is expected.
+    ExpectedDirectory,
+    /// A directory is given where a file is expected.
+    ExpectedFile,
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserError::Unexpected(w) => write!(f, "Unexpected token: {:?}", w),
+            ParserError::UnexpectedEnd => f.write_str("Unexpected end of the configuration file"),
+            ParserError::Redefined(v) => write!(f, "{:?} is already defined", v),
+            ParserError::SameSecretVariable(s, other) => write!(
+                f,
+                "The secrets {:?} and {:?} use the same environment variable",
+                s, other
+            ),
+            ParserError::Undefined(v) => write!(f, "The variable {:?} is not defined", v),
+            ParserError::InvalidPath(p) => write!(
+                f,
+                "The path {:?} must be relative and inside the repository",
+                p
+            ),
+            ParserError::InvalidGlob(g) => {
+                write!(f, "The glob {:?} does not include any file", g)
+            }
+            ParserError::InvalidOption(o) => write!(f, "Invalid or duplicated option {:?}", o),
+            ParserError::InvalidInput(i) => write!(f, "Invalid or duplicated input {:?}", i),
+            ParserError::InvalidKey(k) => write!(f, "Invalid key {:?} in the directory", k),
+            ParserError::DuplicateKey(k) => {
+                write!(f, "The key {:?} is already in the directory", k)
+            }
+            ParserError::OverlappingKey(k, other) => {
+                write!(f, "The key {:?} overlaps the key {:?}", k, other)
+            }
+            ParserError::ExpectedDirectory => f.write_str("Expected a directory, found a file"),
+            ParserError::ExpectedFile => f.write_str("Expected a file, found a directory"),
+        }
+    }
+}
+impl Error for ParserError {}
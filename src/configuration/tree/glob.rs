@@ -0,0 +1,272 @@
+//! The glob file literals, like `"src/**/*.rs;!src/gen/**"`, select several
+//! repository files into a directory.
+
+use super::Directory;
+use crate::walk;
+use std::path::Path;
+use std::{fs, io};
+
+/// The file with the patterns of the repository files never selected by a
+/// glob, one by line. Empty lines and lines beginning with `#` are skipped.
+/// Only the file at the root of the repository is read, and a pattern can not
+/// be negated with `!`.
+pub const IGNORE_FILE: &str = ".cageignore";
+
+/// The directories at the root of the repository never selected by a glob: the
+/// version control, the files of cage and the build outputs.
+pub const NEVER_SELECTED: [&str; 3] = [".git", ".cage", "target"];
+
+/// The patterns of a glob literal, separated by `;`. A pattern beginning with
+/// `!` excludes the files. In a pattern, `*` matches any chars except `/`, `?`
+/// one char and a `**` component any number of directories.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Glob {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+/// The file literal is a glob.
+pub fn is_glob(literal: &str) -> bool {
+    literal.contains(['*', '?', ';']) || literal.starts_with('!')
+}
+
+impl Glob {
+    /// Create the glob from the normalized patterns. None if there is no
+    /// including pattern.
+    pub fn new(patterns: Vec<(bool, String)>) -> Option<Glob> {
+        let mut glob = Glob {
+            include: Vec::new(),
+            exclude: Vec::new(),
+        };
+        for (excluded, p) in patterns {
+            if excluded {
+                glob.exclude.push(p);
+            } else {
+                glob.include.push(p);
+            }
+        }
+        if glob.include.is_empty() {
+            None
+        } else {
+            Some(glob)
+        }
+    }
+
+    /// The directory that contains all selected files, empty for the repository
+    /// or ending with `/`. The paths of the selected files are relative to it.
+    pub fn base(&self) -> &str {
+        /// The length of the directories before the first wildcard.
+        fn static_dir(p: &str) -> usize {
+            let wildcard = p.find(['*', '?']).unwrap_or(p.len());
+            p[..wildcard].rfind('/').map_or(0, |i| i + 1)
+        }
+        let first = &self.include[0];
+        let mut len = static_dir(first);
+        for p in self.include.iter().skip(1) {
+            len = first[..len]
+                .split_inclusive('/')
+                .zip(p[..static_dir(p)].split_inclusive('/'))
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a.len())
+                .sum();
+        }
+        &first[..len]
+    }
+
+    /// The repository file `path` is selected by the glob.
+    pub fn matches(&self, path: &str) -> bool {
+        self.include.iter().any(|p| matches(p, path))
+            && !self.exclude.iter().any(|p| matches(p, path))
+    }
+
+    /// List the selected files of the repository `root`, sorted. The files
+    /// matched by [`IGNORE_FILE`] and the directories [`NEVER_SELECTED`] are
+    /// skipped. The links follow the policy of [`walk::files`], so the walk
+    /// stays in the repository.
+    pub fn files(&self, root: &Path) -> io::Result<Vec<String>> {
+        let ignore = match fs::read_to_string(root.join(IGNORE_FILE)) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(|l| l.trim_end_matches('/').to_string())
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let skip =
+            |path: &str| NEVER_SELECTED.contains(&path) || ignore.iter().any(|p| ignored(p, path));
+        match walk::files(root, self.base(), skip) {
+            Ok(files) => Ok(files.into_iter().filter(|f| self.matches(f)).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read the selected files of the repository `root`, listed by [`Glob::files`].
+    pub fn select(&self, root: &Path) -> io::Result<Directory> {
+        let mut directory = Directory::new();
+        for f in self.files(root)? {
+            let content = fs::read(root.join(&f))?;
+            directory.insert(f[self.base().len()..].to_string(), content);
+        }
+        Ok(directory)
+    }
+}
+
+/// The ignore pattern matches the path: a pattern without `/` matches the name
+/// of any file or directory, else it matches from the repository.
+fn ignored(pattern: &str, path: &str) -> bool {
+    if pattern.contains('/') {
+        matches(pattern.trim_start_matches('/'), path)
+    } else {
+        path.rsplit('/')
+            .next()
+            .is_some_and(|name| component(pattern, name))
+    }
+}
+
+/// The pattern matches all components of the path, a `**` component matches
+/// any number of components.
+fn matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    wildcard(&pattern, &path, |p| *p == "**", |p, c| component(p, c))
+}
+
+/// The pattern matches one component of a path.
+fn component(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    wildcard(&pattern, &name, |p| *p == '*', |p, c| *p == '?' || p == c)
+}
+
+/// The pattern matches all the items, a `star` matches any number of items and
+/// the other pattern items match one item with `one`. Only the last star is
+/// tried again after a mismatch, so the time is at most the product of the
+/// lengths, whatever the number of stars.
+fn wildcard<P, T>(
+    pattern: &[P],
+    items: &[T],
+    star: impl Fn(&P) -> bool,
+    one: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // The index of the last star and of the first item it does not match yet.
+    let mut last_star: Option<(usize, usize)> = None;
+    while i < items.len() {
+        if p < pattern.len() && star(&pattern[p]) {
+            last_star = Some((p, i));
+            p += 1;
+        } else if p < pattern.len() && one(&pattern[p], &items[i]) {
+            p += 1;
+            i += 1;
+        } else if let Some((s, matched)) = last_star {
+            p = s + 1;
+            i = matched + 1;
+            last_star = Some((s, i));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(star)
+}
+
+#[test]
+fn test_glob() {
+    let glob = |patterns: &[&str]| {
+        Glob::new(
+            patterns
+                .iter()
+                .map(|p| match p.strip_prefix('!') {
+                    Some(p) => (true, p.to_string()),
+                    None => (false, p.to_string()),
+                })
+                .collect(),
+        )
+        .unwrap()
+    };
+
+    let rust = glob(&["src/**/*.rs", "!src/gen/**"]);
+    assert_eq!("src/", rust.base());
+    assert!(rust.matches("src/main.rs"));
+    assert!(rust.matches("src/a/b/c.rs"));
+    assert!(!rust.matches("src/gen/out.rs"));
+    assert!(!rust.matches("src/main.rs.orig"));
+    assert!(!rust.matches("tests/main.rs"));
+    assert_eq!("", glob(&["*.md"]).base());
+    assert_eq!("doc/", glob(&["doc/a/*.md", "doc/b?/*.md"]).base());
+    assert!(glob(&["doc/b?/*.md"]).matches("doc/b1/x.md"));
+    assert_eq!(None, Glob::new(vec![(true, "a".to_string())]));
+
+    let dir = crate::temp::TempDir::new("glob");
+    for (path, content) in [
+        ("src/main.rs", "main"),
+        ("src/a/lib.rs", "lib"),
+        ("src/a/lib.txt", "txt"),
+        ("src/gen/out.rs", "out"),
+        ("src/target/skip.rs", "skip"),
+        ("src/b/skip.rs", "skip"),
+        ("target/skip.rs", "skip"),
+        (".git/skip.rs", "skip"),
+        ("main.rs", "main"),
+        (".cageignore", "# Comment\n\ntarget/\n/src/b\n"),
+    ]
+    .iter()
+    {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    }
+
+    let selected = rust.select(&dir).unwrap();
+    assert_eq!(
+        vec!["a/lib.rs", "main.rs"],
+        selected.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(Some(&b"lib".to_vec()), selected.get("a/lib.rs"));
+    assert!(glob(&["none/*.rs"]).select(&dir).unwrap().is_empty());
+    assert_eq!(
+        vec!["main.rs", "src/a/lib.rs", "src/gen/out.rs", "src/main.rs"],
+        glob(&["**/*.rs"]).files(&dir).unwrap()
+    );
+}
+
+#[test]
+fn test_glob_wildcard() {
+    assert!(component("*.tar.*", "a.tar.gz"));
+    assert!(component("a*b?c*", "axxbyc"));
+    assert!(!component("a*b?c", "axxbycd"));
+    assert!(component("*", ""));
+    assert!(matches("**", "a/b"));
+    assert!(matches("a/**/b/**/c", "a/b/x/c"));
+    assert!(!matches("a/**/b", "a/c"));
+
+    // Many wildcards must not take an exponential time.
+    let name = "a".repeat(60);
+    assert!(!component(&format!("{}b", "*a".repeat(30)), &name));
+    let path = vec!["a"; 60].join("/");
+    assert!(!matches(&format!("{}b", "**/a/".repeat(30)), &path));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_glob_link() {
+    let dir = crate::temp::TempDir::new("glob-link");
+    let root = dir.join("repo");
+    fs::create_dir_all(root.join("src/a")).unwrap();
+    fs::write(root.join("src/a/lib.rs"), "lib").unwrap();
+    fs::write(dir.join("outside.rs"), "outside").unwrap();
+    std::os::unix::fs::symlink("a", root.join("src/link")).unwrap();
+    std::os::unix::fs::symlink("..", root.join("src/a/loop")).unwrap();
+
+    let glob = Glob::new(vec![(false, "src/**/*.rs".to_string())]).unwrap();
+    assert_eq!(
+        vec!["a/lib.rs", "link/lib.rs"],
+        glob.select(&root).unwrap().keys().collect::<Vec<_>>()
+    );
+
+    // A link out of the repository is not walked, even to a parent.
+    std::os::unix::fs::symlink("../../..", root.join("src/a/up")).unwrap();
+    assert!(glob.select(&root).is_err());
+}
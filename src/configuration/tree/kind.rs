@@ -0,0 +1,138 @@
+use super::super::{ConfigurationError, Position};
+use super::{Generator, Object, ObjectValue, ParserError, Pipe, Tree};
+use std::collections::HashMap;
+
+impl Object {
+    /// The object is a directory, else a file. `definitions` gives the kind of
+    /// the variables, true for a directory.
+    pub fn is_dir(&self, definitions: &HashMap<String, bool>) -> bool {
+        match &self.value {
+            ObjectValue::Aggregation(_)
+            | ObjectValue::Composition(..)
+            | ObjectValue::Glob(_)
+            | ObjectValue::Inputs(_) => true,
+            ObjectValue::Pipe(Pipe { output_is_dir, .. }) => *output_is_dir,
+            ObjectValue::Select(_, path) => path.ends_with('/'),
+            // The empty path is the repository.
+            ObjectValue::File(f) => f.is_empty() || f.ends_with('/'),
+            ObjectValue::Literal(_) => false,
+            ObjectValue::Variable(v) => definitions.get(v).copied().unwrap_or(false),
+        }
+    }
+
+    /// Check the kinds of the sub objects: the input of a pipe with the
+    /// operator, the pipeline generators are files, a sub path is selected in
+    /// a directory, the elements of a composition are directories, and the values
+    /// of aggregation keys ending with `/` are directories.
+    fn check_kinds(&self, definitions: &HashMap<String, bool>) -> Result<(), ConfigurationError> {
+        match &self.value {
+            ObjectValue::Composition(list, _) => {
+                for o in list.iter() {
+                    o.check_kinds(definitions)?;
+                    expect(o.position, true, o.is_dir(definitions))?;
+                }
+            }
+            ObjectValue::Aggregation(list) => {
+                for (key, o) in list.iter() {
+                    o.check_kinds(definitions)?;
+                    if key.ends_with('/') {
+                        expect(o.position, true, o.is_dir(definitions))?;
+                    }
+                }
+            }
+            ObjectValue::Inputs(list) => {
+                for (_, o) in list.iter() {
+                    o.check_kinds(definitions)?;
+                }
+            }
+            ObjectValue::Select(object, _) => {
+                object.check_kinds(definitions)?;
+                expect(self.position, true, object.is_dir(definitions))?;
+            }
+            ObjectValue::Pipe(Pipe {
+                input,
+                generator,
+                input_is_dir,
+                ..
+            }) => {
+                input.check_kinds(definitions)?;
+                if let Some(expected) = input_is_dir {
+                    expect(self.position, *expected, input.is_dir(definitions))?;
+                }
+                // The generator module is a file.
+                if let Generator::Pipeline(pipeline) = generator {
+                    pipeline.check_kinds(definitions)?;
+                    expect(pipeline.position, false, pipeline.is_dir(definitions))?;
+                }
+            }
+            ObjectValue::File(_)
+            | ObjectValue::Glob(_)
+            | ObjectValue::Literal(_)
+            | ObjectValue::Variable(_) => {}
+        };
+        Ok(())
+    }
+}
+
+/// Return an error if the found kind is not the expected one.
+fn expect(position: Position, expected_dir: bool, is_dir: bool) -> Result<(), ConfigurationError> {
+    match (expected_dir, is_dir) {
+        (true, false) => Err(ConfigurationError::Parser(
+            position,
+            ParserError::ExpectedDirectory,
+        )),
+        (false, true) => Err(ConfigurationError::Parser(
+            position,
+            ParserError::ExpectedFile,
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl Tree {
+    /// Infer the kind of each object and check it, a `file` definition must be
+    /// a file and a `dir` definition a directory.
+    pub fn check_kinds(&self) -> Result<(), ConfigurationError> {
+        let mut definitions = HashMap::new();
+        for d in self.definitions.iter() {
+            d.value.check_kinds(&definitions)?;
+            expect(d.value.position, d.is_dir, d.value.is_dir(&definitions))?;
+            definitions.insert(d.key.name().to_string(), d.is_dir);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_kinds() {
+    let kind_err = |config: &str| match Tree::parse(config) {
+        Err(ConfigurationError::Parser(p, e)) => (p.line, p.column, e),
+        r => panic!("Expected a parser error, get {:?}", r),
+    };
+    Tree::parse(
+        "CAGE-BUILD-1
+file a \"a.txt\"
+dir b \"b/\" >| \"g\" |> \"h\"
+dir c [b, { \"x/\": b, \"y\": a, \"z\": $\"z\" }, \"\"]
+file d c >| \"i\"
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        (2, 8, ParserError::ExpectedFile),
+        kind_err("CAGE-BUILD-0\nfile a \"a/\"\n")
+    );
+    assert_eq!(
+        (2, 11, ParserError::ExpectedDirectory),
+        kind_err("CAGE-BUILD-0\ndir a \"b\" | \"g\"\n")
+    );
+    assert_eq!(
+        (3, 14, ParserError::ExpectedDirectory),
+        kind_err("CAGE-BUILD-0\nfile a $\"a\"\ndir b [\"b/\", a]\n")
+    );
+    assert_eq!(
+        (2, 15, ParserError::ExpectedDirectory),
+        kind_err("CAGE-BUILD-0\ndir b { \"x/\": \"x\" }\n")
+    );
+}
@@ -1,12 +1,50 @@
+mod build;
+mod cache;
+mod compose;
+mod dependency;
+mod each;
+mod error;
+mod glob;
+mod kind;
 mod object;
+mod parser;
+mod path;
+mod select;
 
 use super::Position;
 use std::collections::{HashMap, HashSet};
 
-struct Tree(Vec<Definition>);
+pub use build::{BuildError, Output, Outputs};
+pub use cache::Cache;
+pub use compose::Conflict;
+#[allow(unused_imports)]
+pub use compose::{compose, CompositionError, Directory};
+pub use error::ParserError;
+#[allow(unused_imports)]
+pub use glob::{Glob, IGNORE_FILE};
+#[allow(unused_imports)]
+pub use select::select;
+
+/// The configuration file, the definitions, the declared tags and secrets.
+#[derive(Debug)]
+pub struct Tree {
+    definitions: Vec<Definition>,
+    tags: Vec<Declaration>,
+    secrets: Vec<Declaration>,
+}
+
+/// A declared tag or secret.
+#[derive(Debug)]
+struct Declaration {
+    position: Position,
+    name: String,
+}
 
+#[derive(Debug)]
 struct Definition {
     position: Position,
+    /// Defined with the keyword `dir`.
+    is_dir: bool,
     key: DefinitionKey,
     value: Object,
 }
@@ -27,9 +65,21 @@ struct Object {
 
 #[derive(Debug, PartialEq, Clone)]
 enum ObjectValue {
+    /// The keys are normalized paths, `"a/b": x` creates the directory `a`.
     Aggregation(Vec<(String, Object)>),
-    Composition(Vec<Object>),
+    Composition(Vec<Object>, Conflict),
+    /// A sub path of a directory, `object / "lib/"`, a directory if the path
+    /// ends with `/`. On a repository directory, it is lowered into a [`ObjectValue::File`].
+    Select(Box<Object>, String),
+    /// The named inputs of a generator, only as the input of a pipe. Each one
+    /// is mounted separately for the generator.
+    Inputs(Vec<(String, Object)>),
     Pipe(Pipe),
+    /// A file or a directory from the repository.
+    File(String),
+    /// The repository files selected by a glob, it's a directory.
+    Glob(Glob),
+    /// A literal string, used as a file content.
     Literal(String),
     Variable(String),
 }
@@ -38,7 +88,14 @@ enum ObjectValue {
 struct Pipe {
     input: Box<Object>,
     generator: Generator,
+    /// The input kind required by the operator, None if the operator does not say it.
+    input_is_dir: Option<bool>,
     output_is_dir: bool,
+    /// The generator is called for each file of the input directory, `*|`.
+    each: bool,
+    /// The options given to the generator, `{"name": $"value"}`, in the order
+    /// of the file.
+    options: Vec<(String, String)>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -47,6 +104,8 @@ enum Generator {
     Path(String),
     Default(GeneratorDefault),
     Variable(String),
+    /// A pipeline between parentheses, its output file is the generator module.
+    Pipeline(Box<Object>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -58,7 +117,17 @@ struct GeneratorDefault {
 impl Tree {
     /// Return the number of definition.
     fn number_of_definition(&self) -> usize {
-        self.0.len()
+        self.definitions.len()
+    }
+
+    /// Iter over the name of declared tags.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(|t| t.name.as_str())
+    }
+
+    /// Iter over the name of declared secrets.
+    pub fn secrets(&self) -> impl Iterator<Item = &str> {
+        self.secrets.iter().map(|s| s.name.as_str())
     }
 
     /// Get a HastSet with URL of all external generators.
@@ -68,7 +137,7 @@ impl Tree {
     ) -> HashSet<&'a str> {
         let mut h = HashSet::new();
 
-        self.0.iter().for_each(|def| {
+        self.definitions.iter().for_each(|def| {
             def.value.walk(|o| {
                 match &o.value {
                     ObjectValue::Pipe(Pipe { generator, .. }) => match &generator {
@@ -78,9 +147,16 @@ impl Tree {
                         Generator::Url(s) => {
                             h.insert(s.as_str());
                         }
-                        Generator::Variable(_) | Generator::Path(_) => {}
+                        Generator::Variable(_) | Generator::Path(_) | Generator::Pipeline(_) => {}
                     },
-                    _ => {}
+                    ObjectValue::Aggregation(_)
+                    | ObjectValue::Composition(..)
+                    | ObjectValue::Select(..)
+                    | ObjectValue::Inputs(_)
+                    | ObjectValue::File(_)
+                    | ObjectValue::Glob(_)
+                    | ObjectValue::Literal(_)
+                    | ObjectValue::Variable(_) => {}
                 };
             })
         });
@@ -101,7 +177,10 @@ fn generator_url_list() {
             value: ObjectValue::Pipe(Pipe {
                 input: Box::new(src),
                 generator: gen,
+                input_is_dir: None,
                 output_is_dir: true,
+                each: false,
+                options: Vec::new(),
             }),
         }
     }
@@ -136,11 +215,16 @@ fn generator_url_list() {
 
     assert_eq!(
         generators,
-        Tree(vec![Definition {
-            position: p,
-            key: DefinitionKey::SystemRelease,
-            value: root,
-        }])
+        Tree {
+            definitions: vec![Definition {
+                position: p,
+                is_dir: true,
+                key: DefinitionKey::SystemRelease,
+                value: root,
+            }],
+            tags: Vec::new(),
+            secrets: Vec::new(),
+        }
         .generator_url_list(&default)
     );
 }
@@ -1,4 +1,4 @@
-use super::{Object, ObjectValue, Pipe};
+use super::{Generator, Object, ObjectValue, Pipe};
 
 impl Object {
     /// Iter over object and sub child object.
@@ -21,17 +21,30 @@ impl Object {
     {
         f(self);
         match &self.value {
-            ObjectValue::Aggregation(list) => list.iter().for_each(move |(_, o)| o.walk_inter(f)),
-            ObjectValue::Composition(list) => list.iter().for_each(move |o| o.walk_inter(f)),
-            ObjectValue::Pipe(Pipe { input, .. }) => input.walk_inter(f),
-            ObjectValue::Literal(_) | ObjectValue::Variable(_) => {}
+            ObjectValue::Aggregation(list) | ObjectValue::Inputs(list) => {
+                list.iter().for_each(move |(_, o)| o.walk_inter(f))
+            }
+            ObjectValue::Composition(list, _) => list.iter().for_each(move |o| o.walk_inter(f)),
+            ObjectValue::Select(object, _) => object.walk_inter(f),
+            ObjectValue::Pipe(Pipe {
+                input, generator, ..
+            }) => {
+                input.walk_inter(f);
+                if let Generator::Pipeline(pipeline) = generator {
+                    pipeline.walk_inter(f);
+                }
+            }
+            ObjectValue::File(_)
+            | ObjectValue::Glob(_)
+            | ObjectValue::Literal(_)
+            | ObjectValue::Variable(_) => {}
         };
     }
 }
 
 #[test]
 fn test_object_iter() {
-    use super::{Generator, ObjectValue, Pipe, Position};
+    use super::{Conflict, Position};
 
     let p = Position { line: 0, column: 0 };
     let pipe_src = Object {
@@ -56,7 +69,10 @@ fn test_object_iter() {
             value: ObjectValue::Pipe(Pipe {
                 input: Box::new(pipe_src.clone()),
                 generator: Generator::Variable(String::from("var")),
+                input_is_dir: None,
                 output_is_dir: true,
+                each: false,
+                options: Vec::new(),
             }),
         },
         Object {
@@ -66,7 +82,7 @@ fn test_object_iter() {
     ];
     let root = Object {
         position: p,
-        value: ObjectValue::Composition(composition_intern.clone()),
+        value: ObjectValue::Composition(composition_intern.clone(), Conflict::Error),
     };
 
     println!("root: {:#?}", root);
@@ -0,0 +1,842 @@
+use super::super::{
+    cst::{Cst, Node, NodeKind},
+    lexer::{unescape, Word},
+    version::Version,
+    ConfigurationError, Position,
+};
+use super::glob::{is_glob, Glob};
+use super::path::normalize;
+use super::{
+    Conflict, Declaration, Definition, DefinitionKey, Generator, GeneratorDefault, Object,
+    ObjectValue, ParserError, Pipe, Tree,
+};
+use crate::api::env_name;
+use std::collections::{HashMap, HashSet};
+
+/// Create the tree from the concrete syntax tree, the grammar is already checked
+/// so only the variables and the paths are checked.
+struct Lowering {
+    /// The file literals are globs only from CAGE-BUILD-1.
+    version: Version,
+    /// The defined variables and system variables.
+    defined: HashSet<String>,
+    /// The repository directory of the variables defined by a directory
+    /// literal, directly or by another variable.
+    directories: HashMap<String, String>,
+}
+
+impl Tree {
+    /// Parse the configuration file, with its header.
+    pub fn parse(config: &str) -> Result<Tree, ConfigurationError> {
+        Tree::from_cst(&Cst::parse(config)?)
+    }
+
+    /// Create the tree from the concrete syntax tree.
+    pub fn from_cst(cst: &Cst) -> Result<Tree, ConfigurationError> {
+        let tree = Lowering {
+            version: cst.version(),
+            defined: HashSet::new(),
+            directories: HashMap::new(),
+        }
+        .tree(cst.root())?;
+        tree.check_kinds()?;
+        Ok(tree)
+    }
+}
+
+impl DefinitionKey {
+    /// The name of the key, with the dollar for system variables.
+    pub fn name(&self) -> &str {
+        match self {
+            DefinitionKey::SystemRelease => "$pkg",
+            DefinitionKey::SystemRun => "$run",
+            DefinitionKey::SystemTest => "$test",
+            DefinitionKey::Variable(v) => v,
+        }
+    }
+}
+
+fn err<T>(position: Position, e: ParserError) -> Result<T, ConfigurationError> {
+    Err(ConfigurationError::Parser(position, e))
+}
+
+impl Lowering {
+    /// Unescape the content of a file path or a literal string. CAGE-BUILD-0
+    /// has no escapes, the content is kept.
+    fn text(&self, raw: &str) -> String {
+        match self.version {
+            Version::V0 => raw.to_string(),
+            _ => unescape(raw).expect("The escapes are checked by the lexer"),
+        }
+    }
+
+    /// Unescape and normalize a file path. In CAGE-BUILD-0 a backslash is a
+    /// separator, from CAGE-BUILD-1 an escaped backslash is an invalid path.
+    fn path(&self, position: Position, raw: &str) -> Result<String, ConfigurationError> {
+        let p = match self.version {
+            Version::V0 => raw.replace('\\', "/"),
+            _ => self.text(raw),
+        };
+        match normalize(&p) {
+            Some(p) => Ok(p),
+            None => err(position, ParserError::InvalidPath(self.text(raw))),
+        }
+    }
+
+    /// Unescape and normalize the patterns of a glob, separated by `;`.
+    fn glob(&self, position: Position, raw: &str) -> Result<Glob, ConfigurationError> {
+        let mut patterns = Vec::new();
+        for p in self.text(raw).split(';').filter(|p| !p.is_empty()) {
+            let (excluded, p) = match p.strip_prefix('!') {
+                Some(p) => (true, p),
+                None => (false, p),
+            };
+            match normalize(p) {
+                Some(n) => patterns.push((excluded, n)),
+                None => return err(position, ParserError::InvalidPath(p.to_string())),
+            }
+        }
+        match Glob::new(patterns) {
+            Some(g) => Ok(g),
+            None => err(position, ParserError::InvalidGlob(self.text(raw))),
+        }
+    }
+
+    /// Unescape, normalize and check an aggregation key with the previous keys.
+    fn key(
+        &self,
+        position: Position,
+        raw: &str,
+        previous: &[(String, Object)],
+    ) -> Result<String, ConfigurationError> {
+        let key = self.path(position, raw)?;
+        let invalid = key.is_empty()
+            || self
+                .text(raw)
+                .replace('\\', "/")
+                .split('/')
+                .any(|s| s == "..")
+            || key.chars().any(char::is_control);
+        if invalid {
+            return err(position, ParserError::InvalidKey(self.text(raw)));
+        }
+
+        let name = key.trim_end_matches('/');
+        for (other, _) in previous.iter() {
+            let other_name = other.trim_end_matches('/');
+            if name == other_name {
+                return err(position, ParserError::DuplicateKey(key));
+            }
+            let inside = |a: &str, b: &str| a.strip_prefix(b).is_some_and(|r| r.starts_with('/'));
+            if inside(name, other_name) || inside(other_name, name) {
+                return err(position, ParserError::OverlappingKey(key, other.clone()));
+            }
+        }
+        Ok(key)
+    }
+}
+
+impl Lowering {
+    /// The repository directory given by a directory literal or by a variable
+    /// defined by one.
+    fn directory<'a>(&'a self, value: &'a ObjectValue) -> Option<&'a str> {
+        match value {
+            ObjectValue::File(f) if f.is_empty() || f.ends_with('/') => Some(f),
+            ObjectValue::Variable(v) => self.directories.get(v).map(String::as_str),
+            _ => None,
+        }
+    }
+
+    /// Return an error if the variable is not defined.
+    fn check_defined(&self, position: Position, v: &str) -> Result<(), ConfigurationError> {
+        if self.defined.contains(v) {
+            Ok(())
+        } else {
+            err(position, ParserError::Undefined(v.to_string()))
+        }
+    }
+
+    fn tree(mut self, root: &Node) -> Result<Tree, ConfigurationError> {
+        let mut tree = Tree {
+            definitions: Vec::new(),
+            tags: Vec::new(),
+            secrets: Vec::new(),
+        };
+
+        for node in root.nodes() {
+            let (token, word) = node.words().nth(1).unwrap();
+            let position = token.position;
+            match node.kind {
+                NodeKind::Tag | NodeKind::Secret => {
+                    let name = match word {
+                        Word::Variable(name) => name.clone(),
+                        _ => unreachable!(),
+                    };
+                    let list = if node.kind == NodeKind::Tag {
+                        &mut tree.tags
+                    } else {
+                        &mut tree.secrets
+                    };
+                    if list.iter().any(|d| d.name == name) {
+                        return err(position, ParserError::Redefined(name));
+                    } else if node.kind == NodeKind::Secret {
+                        let same = list.iter().find(|d| env_name(&d.name) == env_name(&name));
+                        if let Some(other) = same {
+                            let other = other.name.clone();
+                            return err(position, ParserError::SameSecretVariable(name, other));
+                        }
+                    }
+                    list.push(Declaration { position, name });
+                }
+                NodeKind::Definition => {
+                    let key = match word {
+                        Word::SystemPackage => DefinitionKey::SystemRelease,
+                        Word::SystemRun => DefinitionKey::SystemRun,
+                        Word::SystemTest => DefinitionKey::SystemTest,
+                        Word::Variable(v) => DefinitionKey::Variable(v.clone()),
+                        _ => unreachable!(),
+                    };
+                    if self.defined.contains(key.name()) {
+                        return err(position, ParserError::Redefined(key.name().to_string()));
+                    }
+                    let is_dir = node.words().next().unwrap().1 == &Word::KeywordDir;
+                    let value = self.object(node.nodes().next().unwrap())?;
+                    self.defined.insert(key.name().to_string());
+                    if let Some(dir) = self.directory(&value.value) {
+                        let dir = dir.to_string();
+                        self.directories.insert(key.name().to_string(), dir);
+                    }
+                    tree.definitions.push(Definition {
+                        position,
+                        is_dir,
+                        key,
+                        value,
+                    });
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(tree)
+    }
+
+    /// Lower an object node.
+    fn object(&self, node: &Node) -> Result<Object, ConfigurationError> {
+        let (token, word) = node.words().next().unwrap();
+        let position = token.position;
+        let value = match node.kind {
+            NodeKind::Pipe => {
+                let mut nodes = node.nodes();
+                let input = self.object(nodes.next().unwrap())?;
+                if matches!(input.value, ObjectValue::Inputs(_)) && *word == Word::PipeEach {
+                    return err(position, ParserError::Unexpected(Word::PipeEach));
+                }
+                let generator = self.generator(nodes.next().unwrap())?;
+                let (input_is_dir, output_is_dir) = word.pipe_kinds().unwrap();
+                let mut options: Vec<(String, String)> = Vec::new();
+                for entry in nodes.next().iter().flat_map(|n| n.nodes()) {
+                    match entry.words().collect::<Vec<_>>()[..] {
+                        [(t, Word::File(name)), _, (_, Word::String(value))] => {
+                            let name = self.text(name);
+                            if name.is_empty() || options.iter().any(|(n, _)| *n == name) {
+                                return err(t.position, ParserError::InvalidOption(name));
+                            }
+                            options.push((name, self.text(value)));
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                ObjectValue::Pipe(Pipe {
+                    input: Box::new(input),
+                    generator,
+                    input_is_dir,
+                    output_is_dir,
+                    each: *word == Word::PipeEach,
+                    options,
+                })
+            }
+            NodeKind::Aggregation => {
+                let mut list = Vec::new();
+                for entry in node.nodes() {
+                    let name = match entry.words().next().unwrap() {
+                        (t, Word::File(name)) => self.key(t.position, name, &list)?,
+                        _ => unreachable!(),
+                    };
+                    list.push((name, self.object(entry.nodes().next().unwrap())?));
+                }
+                ObjectValue::Aggregation(list)
+            }
+            NodeKind::Select => {
+                let object = self.object(node.nodes().next().unwrap())?;
+                let sub = match node.words().nth(1).unwrap() {
+                    (t, Word::File(raw)) => {
+                        let sub = self.path(t.position, raw)?;
+                        if sub.is_empty() {
+                            return err(t.position, ParserError::InvalidPath(self.text(raw)));
+                        }
+                        sub
+                    }
+                    _ => unreachable!(),
+                };
+                // A sub path of a repository directory is a longer path, so
+                // only it is watched, also through a variable.
+                match self.directory(&object.value) {
+                    Some(dir) => {
+                        return Ok(Object {
+                            position: object.position,
+                            value: ObjectValue::File(format!("{}{}", dir, sub)),
+                        })
+                    }
+                    None => ObjectValue::Select(Box::new(object), sub),
+                }
+            }
+            NodeKind::Inputs => {
+                let mut list: Vec<(String, Object)> = Vec::new();
+                for entry in node.nodes() {
+                    let name = match entry.words().next().unwrap() {
+                        (t, Word::File(name)) => {
+                            let name = self.text(name);
+                            if name.is_empty()
+                                || name.contains(['/', '\\'])
+                                || list.iter().any(|(n, _)| *n == name)
+                            {
+                                return err(t.position, ParserError::InvalidInput(name));
+                            }
+                            name
+                        }
+                        _ => unreachable!(),
+                    };
+                    list.push((name, self.object(entry.nodes().next().unwrap())?));
+                }
+                ObjectValue::Inputs(list)
+            }
+            NodeKind::Composition => ObjectValue::Composition(
+                node.nodes()
+                    .map(|n| self.object(n))
+                    .collect::<Result<_, _>>()?,
+                if *word == Word::KeywordOverride {
+                    Conflict::Override
+                } else {
+                    Conflict::Error
+                },
+            ),
+            _ => match word {
+                Word::File(f) if self.version != Version::V0 && is_glob(&self.text(f)) => {
+                    ObjectValue::Glob(self.glob(position, f)?)
+                }
+                Word::File(f) => ObjectValue::File(self.path(position, f)?),
+                Word::String(s) => ObjectValue::Literal(self.text(s)),
+                Word::Variable(v) => {
+                    self.check_defined(position, v)?;
+                    ObjectValue::Variable(v.clone())
+                }
+                _ => unreachable!(),
+            },
+        };
+        Ok(Object { position, value })
+    }
+
+    /// Lower the generator node, after the pipe.
+    fn generator(&self, node: &Node) -> Result<Generator, ConfigurationError> {
+        Ok(match node.words().collect::<Vec<_>>()[..] {
+            [(_, Word::File(default_name)), _, (_, Word::String(url))] => {
+                Generator::Default(GeneratorDefault {
+                    default_name: self.text(default_name),
+                    url: self.text(url),
+                })
+            }
+            [(t, Word::File(p))] => Generator::Path(self.path(t.position, p)?),
+            [(_, Word::String(url))] => Generator::Url(self.text(url)),
+            [(t, Word::Variable(v))] => {
+                self.check_defined(t.position, v)?;
+                Generator::Variable(v.clone())
+            }
+            [(_, Word::ParenthesisOpen), _] => {
+                Generator::Pipeline(Box::new(self.object(node.nodes().next().unwrap())?))
+            }
+            _ => unreachable!(),
+        })
+    }
+}
+
+#[test]
+fn test_parser() {
+    let tree = Tree::parse(
+        r##"CAGE-BUILD-0
+# A comment
+tag superTag
+
+dir front "front/" > "min" ?? $"https://exemple.com/minifier"
+
+dir $pkg [
+	{
+		"file.txt": $"A literal string.",
+		"min/": front,
+	},
+	front | "gen.wasm" > $"https://exemple.com/gen",
+]
+"##,
+    )
+    .unwrap();
+
+    let p = |line, column| Position { line, column };
+    assert_eq!(vec!["superTag"], tree.tags().collect::<Vec<_>>());
+    assert_eq!(p(3, 5), tree.tags[0].position);
+    assert_eq!(2, tree.number_of_definition());
+
+    let front = &tree.definitions[0];
+    assert_eq!(p(5, 5), front.position);
+    assert_eq!("front", front.key.name());
+    assert_eq!(
+        Object {
+            position: p(5, 20),
+            value: ObjectValue::Pipe(Pipe {
+                input: Box::new(Object {
+                    position: p(5, 11),
+                    value: ObjectValue::File("front/".to_string()),
+                }),
+                generator: Generator::Default(GeneratorDefault {
+                    default_name: "min".to_string(),
+                    url: "https://exemple.com/minifier".to_string(),
+                }),
+                input_is_dir: None,
+                output_is_dir: true,
+                each: false,
+                options: Vec::new(),
+            }),
+        },
+        front.value
+    );
+
+    let pkg = &tree.definitions[1];
+    assert_eq!("$pkg", pkg.key.name());
+    assert_eq!(
+        Object {
+            position: p(7, 10),
+            value: ObjectValue::Composition(
+                vec![
+                    Object {
+                        position: p(8, 2),
+                        value: ObjectValue::Aggregation(vec![
+                            (
+                                "file.txt".to_string(),
+                                Object {
+                                    position: p(9, 15),
+                                    value: ObjectValue::Literal("A literal string.".to_string()),
+                                }
+                            ),
+                            (
+                                "min/".to_string(),
+                                Object {
+                                    position: p(10, 11),
+                                    value: ObjectValue::Variable("front".to_string()),
+                                }
+                            ),
+                        ]),
+                    },
+                    Object {
+                        position: p(12, 21),
+                        value: ObjectValue::Pipe(Pipe {
+                            input: Box::new(Object {
+                                position: p(12, 8),
+                                value: ObjectValue::Pipe(Pipe {
+                                    input: Box::new(Object {
+                                        position: p(12, 2),
+                                        value: ObjectValue::Variable("front".to_string()),
+                                    }),
+                                    generator: Generator::Path("gen.wasm".to_string()),
+                                    input_is_dir: None,
+                                    output_is_dir: false,
+                                    each: false,
+                                    options: Vec::new(),
+                                }),
+                            }),
+                            generator: Generator::Url("https://exemple.com/gen".to_string()),
+                            input_is_dir: None,
+                            output_is_dir: true,
+                            each: false,
+                            options: Vec::new(),
+                        }),
+                    },
+                ],
+                Conflict::Error
+            ),
+        },
+        pkg.value
+    );
+
+    let tree = Tree::parse("CAGE-BUILD-1\ntag key\nsecret key\nsecret other\n").unwrap();
+    assert_eq!(vec!["key"], tree.tags().collect::<Vec<_>>());
+    assert_eq!(vec!["key", "other"], tree.secrets().collect::<Vec<_>>());
+    assert_eq!(p(3, 8), tree.secrets[0].position);
+
+    let tree =
+        Tree::parse("CAGE-BUILD-1\nfile e \"./caf\\u{e9}//\" | $\"a \\\"b\\\"\\n\"\n").unwrap();
+    assert_eq!(
+        ObjectValue::Pipe(Pipe {
+            input: Box::new(Object {
+                position: p(2, 8),
+                value: ObjectValue::File("café/".to_string()),
+            }),
+            generator: Generator::Url("a \"b\"\n".to_string()),
+            input_is_dir: None,
+            output_is_dir: false,
+            each: false,
+            options: Vec::new(),
+        }),
+        tree.definitions[0].value.value
+    );
+
+    let tree =
+        Tree::parse("CAGE-BUILD-1\nfile a \"a/\" >| \"g\"\ndir b a |> \"h\" >> \"i\"\n").unwrap();
+    assert!(!tree.definitions[0].is_dir);
+    assert!(tree.definitions[1].is_dir);
+    match &tree.definitions[1].value.value {
+        ObjectValue::Pipe(Pipe {
+            input,
+            input_is_dir: Some(true),
+            output_is_dir: true,
+            ..
+        }) => assert!(matches!(
+            input.value,
+            ObjectValue::Pipe(Pipe {
+                input_is_dir: Some(false),
+                output_is_dir: true,
+                ..
+            })
+        )),
+        v => panic!("Unexpected value {:?}", v),
+    }
+
+    let tree = Tree::parse("CAGE-BUILD-1\ndir a \"./src/**/*.rs;;!src/gen/**\"\n").unwrap();
+    assert_eq!(
+        ObjectValue::Glob(
+            Glob::new(vec![
+                (false, "src/**/*.rs".to_string()),
+                (true, "src/gen/**".to_string())
+            ])
+            .unwrap()
+        ),
+        tree.definitions[0].value.value
+    );
+
+    let tree = Tree::parse(
+        "CAGE-BUILD-1\nfile a \"a.c\" | (\"gen/\" >| \"rustc\" ?? $\"https://exemple.com/rustc\")\n",
+    )
+    .unwrap();
+    assert_eq!(
+        ObjectValue::Pipe(Pipe {
+            input: Box::new(Object {
+                position: p(2, 8),
+                value: ObjectValue::File("a.c".to_string()),
+            }),
+            generator: Generator::Pipeline(Box::new(Object {
+                position: p(2, 24),
+                value: ObjectValue::Pipe(Pipe {
+                    input: Box::new(Object {
+                        position: p(2, 17),
+                        value: ObjectValue::File("gen/".to_string()),
+                    }),
+                    generator: Generator::Default(GeneratorDefault {
+                        default_name: "rustc".to_string(),
+                        url: "https://exemple.com/rustc".to_string(),
+                    }),
+                    input_is_dir: Some(true),
+                    output_is_dir: false,
+                    each: false,
+                    options: Vec::new(),
+                }),
+            })),
+            input_is_dir: None,
+            output_is_dir: false,
+            each: false,
+            options: Vec::new(),
+        }),
+        tree.definitions[0].value.value
+    );
+
+    let tree = Tree::parse(
+        "CAGE-BUILD-1\nfile a \"a.c\" | \"cc\" {\"level\": $\"3\", \"entry\": $\"main\"} | \"strip\"\n",
+    )
+    .unwrap();
+    match &tree.definitions[0].value.value {
+        ObjectValue::Pipe(Pipe { input, options, .. }) => {
+            assert!(options.is_empty());
+            assert!(matches!(
+                &input.value,
+                ObjectValue::Pipe(Pipe { options, .. }) if options == &vec![
+                    ("level".to_string(), "3".to_string()),
+                    ("entry".to_string(), "main".to_string()),
+                ]
+            ));
+        }
+        v => panic!("Unexpected value {:?}", v),
+    }
+
+    let tree = Tree::parse("CAGE-BUILD-1\ndir a \"js/\" *| \"min\"\n").unwrap();
+    assert!(matches!(
+        tree.definitions[0].value.value,
+        ObjectValue::Pipe(Pipe {
+            input_is_dir: Some(true),
+            output_is_dir: true,
+            each: true,
+            ..
+        })
+    ));
+
+    let tree = Tree::parse(
+        "CAGE-BUILD-1\ndir h \"include/\"\nfile a (\"src\": \"src/\", \"headers\": h) >| \"ld\"\n",
+    )
+    .unwrap();
+    match &tree.definitions[1].value.value {
+        ObjectValue::Pipe(Pipe { input, .. }) => assert_eq!(
+            ObjectValue::Inputs(vec![
+                (
+                    "src".to_string(),
+                    Object {
+                        position: p(3, 16),
+                        value: ObjectValue::File("src/".to_string()),
+                    }
+                ),
+                (
+                    "headers".to_string(),
+                    Object {
+                        position: p(3, 35),
+                        value: ObjectValue::Variable("h".to_string()),
+                    }
+                ),
+            ]),
+            input.value
+        ),
+        v => panic!("Unexpected value {:?}", v),
+    }
+
+    let tree = Tree::parse(
+        "CAGE-BUILD-1\nfile a \"out/\" / \"./lib//\" / \"a.so\"\ndir b a > \"g\" / \"lib/\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        Object {
+            position: p(2, 8),
+            value: ObjectValue::File("out/lib/a.so".to_string()),
+        },
+        tree.definitions[0].value
+    );
+    assert!(matches!(
+        &tree.definitions[1].value,
+        Object {
+            position: Position { line: 3, column: 15 },
+            value: ObjectValue::Select(object, path),
+        } if path == "lib/" && matches!(object.value, ObjectValue::Pipe(_))
+    ));
+
+    let tree = Tree::parse("CAGE-BUILD-1\ndir a override [\"a/\", \"b/\"]\n").unwrap();
+    assert_eq!(p(2, 7), tree.definitions[0].value.position);
+    assert!(matches!(
+        tree.definitions[0].value.value,
+        ObjectValue::Composition(ref list, Conflict::Override) if list.len() == 2
+    ));
+
+    let parse_err = |config: &str| match Tree::parse(config) {
+        Err(ConfigurationError::Parser(p, e)) => (p.line, p.column, e),
+        r => panic!("Expected a parser error, get {:?}", r),
+    };
+    assert_eq!(
+        (2, 14, ParserError::Undefined("b".to_string())),
+        parse_err("CAGE-BUILD-0\nfile a \"a\" > b\n")
+    );
+    assert_eq!(
+        (3, 6, ParserError::Redefined("a".to_string())),
+        parse_err("CAGE-BUILD-0\nfile a \"a\"\nfile a \"b\"\n")
+    );
+    assert_eq!(
+        (3, 5, ParserError::Redefined("t".to_string())),
+        parse_err("CAGE-BUILD-0\ntag t\ntag t\n")
+    );
+    assert_eq!(
+        (3, 8, ParserError::Redefined("s".to_string())),
+        parse_err("CAGE-BUILD-1\nsecret s\nsecret s\n")
+    );
+    assert_eq!(
+        (
+            3,
+            8,
+            ParserError::SameSecretVariable("SignKey".to_string(), "signKey".to_string())
+        ),
+        parse_err("CAGE-BUILD-1\nsecret signKey\nsecret SignKey\n")
+    );
+    assert_eq!(
+        (2, 14, ParserError::InvalidPath("../b".to_string())),
+        parse_err("CAGE-BUILD-0\nfile a \"a\" | \"../b\"\n")
+    );
+    assert_eq!(
+        (2, 9, ParserError::InvalidPath("/etc".to_string())),
+        parse_err("CAGE-BUILD-0\ndir a { \"/etc\": \"b\" }\n")
+    );
+    assert_eq!(
+        (2, 8, ParserError::InvalidPath("\\\\server/a".to_string())),
+        parse_err("CAGE-BUILD-1\nfile a \"\\\\\\\\server/a\"\n")
+    );
+    assert_eq!(
+        (2, 13, ParserError::ExpectedFile),
+        parse_err("CAGE-BUILD-1\nfile a \"a/\" || \"g\"\n")
+    );
+    assert_eq!(
+        (3, 9, ParserError::ExpectedDirectory),
+        parse_err("CAGE-BUILD-1\nfile a $\"a\"\ndir b a >> \"g\"\n")
+    );
+    assert!(matches!(
+        Tree::parse("CAGE-BUILD-1\nfile a \"a\\q\"\n"),
+        Err(ConfigurationError::Lexer(
+            Position {
+                line: 2,
+                column: 10
+            },
+            super::super::lexer::LexerError::InvalidEscape('q')
+        ))
+    ));
+    assert_eq!(
+        (2, 8, ParserError::Unexpected(Word::Comma)),
+        parse_err("CAGE-BUILD-0\ndir a [,]\n")
+    );
+    assert_eq!(
+        (2, 7, ParserError::UnexpectedEnd),
+        parse_err("CAGE-BUILD-0\ndir a [\n")
+    );
+    assert_eq!(
+        (2, 19, ParserError::DuplicateKey("a".to_string())),
+        parse_err("CAGE-BUILD-0\ndir a { \"a\": $\"\", \"./a\": $\"\" }\n")
+    );
+    assert_eq!(
+        (2, 19, ParserError::DuplicateKey("a/".to_string())),
+        parse_err("CAGE-BUILD-0\ndir a { \"a\": $\"\", \"a/\": \"c/\" }\n")
+    );
+    assert_eq!(
+        (
+            2,
+            20,
+            ParserError::OverlappingKey("a/b".to_string(), "a".to_string())
+        ),
+        parse_err("CAGE-BUILD-0\ndir a { \"a\": $\"a\", \"a/b\": $\"b\" }\n")
+    );
+    assert_eq!(
+        (2, 9, ParserError::InvalidKey("a/../b".to_string())),
+        parse_err("CAGE-BUILD-0\ndir a { \"a/../b\": $\"b\" }\n")
+    );
+    assert_eq!(
+        (2, 9, ParserError::InvalidKey("./".to_string())),
+        parse_err("CAGE-BUILD-0\ndir a { \"./\": $\"b\" }\n")
+    );
+    assert_eq!(
+        (2, 7, ParserError::InvalidGlob("!*.md".to_string())),
+        parse_err("CAGE-BUILD-1\ndir a \"!*.md\"\n")
+    );
+    assert_eq!(
+        (2, 7, ParserError::InvalidPath("../*.md".to_string())),
+        parse_err("CAGE-BUILD-1\ndir a \"../*.md\"\n")
+    );
+    assert_eq!(
+        (2, 8, ParserError::ExpectedFile),
+        parse_err("CAGE-BUILD-1\nfile a \"*.md\"\n")
+    );
+    assert_eq!(
+        (2, 20, ParserError::ExpectedFile),
+        parse_err("CAGE-BUILD-1\nfile a \"a\" | (\"g/\" > \"c\")\n")
+    );
+    assert_eq!(
+        (2, 21, ParserError::UnexpectedEnd),
+        parse_err("CAGE-BUILD-1\nfile a \"a\" | (\"g\" | \"c\"\n")
+    );
+    assert_eq!(
+        (2, 30, ParserError::InvalidOption("o".to_string())),
+        parse_err("CAGE-BUILD-1\nfile a \"a\" | \"g\" {\"o\": $\"1\", \"o\": $\"2\"}\n")
+    );
+    assert_eq!(
+        (2, 24, ParserError::Unexpected(Word::File("1".to_string()))),
+        parse_err("CAGE-BUILD-1\nfile a \"a\" | \"g\" {\"o\": \"1\"}\n")
+    );
+    assert_eq!(
+        (2, 11, ParserError::ExpectedDirectory),
+        parse_err("CAGE-BUILD-1\ndir a \"a\" *| \"min\"\n")
+    );
+    assert_eq!(
+        (2, 19, ParserError::InvalidInput("a".to_string())),
+        parse_err("CAGE-BUILD-1\nfile a (\"a\": $\"\", \"a\": $\"\") | \"ld\"\n")
+    );
+    assert_eq!(
+        (3, 1, ParserError::Unexpected(Word::KeywordFile)),
+        parse_err("CAGE-BUILD-1\ndir a (\"a\": $\"\")\nfile b $\"\"\n")
+    );
+    assert_eq!(
+        (2, 19, ParserError::Unexpected(Word::PipeEach)),
+        parse_err("CAGE-BUILD-1\ndir a (\"a\": \"a/\") *| \"min\"\n")
+    );
+    assert_eq!(
+        (2, 14, ParserError::InvalidPath("./".to_string())),
+        parse_err("CAGE-BUILD-1\ndir a \"a/\" / \"./\"\n")
+    );
+    assert_eq!(
+        (2, 18, ParserError::ExpectedDirectory),
+        parse_err("CAGE-BUILD-1\ndir a \"a/\" | \"g\" / \"b/\"\n")
+    );
+    assert_eq!(
+        (
+            2,
+            14,
+            ParserError::Unexpected(Word::Variable("b".to_string()))
+        ),
+        parse_err("CAGE-BUILD-1\ndir a \"a/\" / b\n")
+    );
+    assert_eq!(
+        (2, 16, ParserError::Unexpected(Word::File("a/".to_string()))),
+        parse_err("CAGE-BUILD-1\ndir a override \"a/\"\n")
+    );
+}
+
+#[test]
+fn test_parser_select_variable() {
+    let tree = Tree::parse("CAGE-BUILD-1\ndir src \"src/\"\ndir lib src / \"lib/\"\n").unwrap();
+    assert_eq!(
+        Object {
+            position: Position { line: 3, column: 9 },
+            value: ObjectValue::File("src/lib/".to_string()),
+        },
+        tree.definitions[1].value
+    );
+}
+
+#[test]
+fn test_parser_v0_literal() {
+    // In CAGE-BUILD-0, the glob chars are a part of the file name.
+    let tree = Tree::parse("CAGE-BUILD-0\nfile a \"a*b\"\ndir b \"!c;d?/\"\n").unwrap();
+    assert_eq!(
+        ObjectValue::File("a*b".to_string()),
+        tree.definitions[0].value.value
+    );
+    assert_eq!(
+        ObjectValue::File("!c;d?/".to_string()),
+        tree.definitions[1].value.value
+    );
+
+    // CAGE-BUILD-0 has no escapes, a backslash is a separator.
+    let tree = Tree::parse("CAGE-BUILD-0\nfile a \"src\\main.rs\" | $\"a\\q\"\n").unwrap();
+    assert_eq!(
+        ObjectValue::Pipe(Pipe {
+            input: Box::new(Object {
+                position: Position { line: 2, column: 8 },
+                value: ObjectValue::File("src/main.rs".to_string()),
+            }),
+            generator: Generator::Url("a\\q".to_string()),
+            input_is_dir: None,
+            output_is_dir: false,
+            each: false,
+            options: Vec::new(),
+        }),
+        tree.definitions[0].value.value
+    );
+    // From CAGE-BUILD-1, an escaped backslash is not a separator.
+    assert!(matches!(
+        Tree::parse("CAGE-BUILD-1\nfile a \"a\\\\b\"\n"),
+        Err(ConfigurationError::Parser(_, ParserError::InvalidPath(p))) if p == "a\\b"
+    ));
+}
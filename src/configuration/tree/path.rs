@@ -0,0 +1,47 @@
+/// Normalize a repository path: the separators are `/`, the empty and `.`
+/// components are removed, `..` removes the previous component. The ending
+/// `/` of a directory is kept. None if the path is absolute, goes out of the
+/// repository or has a backslash, it is a separator on Windows. The empty path
+/// is the repository.
+pub fn normalize(path: &str) -> Option<String> {
+    if path.contains('\\') {
+        return None;
+    }
+    let drive = matches!(path.as_bytes(), [l, b':', ..] if l.is_ascii_alphabetic());
+    if path.starts_with('/') || drive {
+        return None;
+    }
+
+    let mut components: Vec<&str> = Vec::new();
+    for c in path.split('/') {
+        match c {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            c => components.push(c),
+        }
+    }
+
+    let mut normalized = components.join("/");
+    let dir = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+    if dir && !normalized.is_empty() {
+        normalized.push('/');
+    }
+    Some(normalized)
+}
+
+#[test]
+fn test_normalize() {
+    let n = |p| normalize(p);
+    assert_eq!(Some("front/".to_string()), n("./front//"));
+    assert_eq!(Some("src/main.rs".to_string()), n("src/bin/../main.rs"));
+    assert_eq!(None, n("src\\main.rs"));
+    assert_eq!(Some("a/".to_string()), n("a/b/.."));
+    assert_eq!(Some("".to_string()), n("./"));
+    assert_eq!(Some("".to_string()), n(""));
+    assert_eq!(None, n("/etc/passwd"));
+    assert_eq!(None, n("C:\\Windows"));
+    assert_eq!(None, n("a/../../b"));
+    assert_eq!(None, n(".."));
+}
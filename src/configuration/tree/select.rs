@@ -0,0 +1,46 @@
+//! Materialize a sub path selection `object / "lib/"` on a generated directory.
+
+use super::{Directory, Output};
+
+/// Get the sub path `path` of the directory. A path ending with `/` gives the
+/// files of the sub directory, relative to it. The selection is a new
+/// directory, so its cache key changes only when the selected files change.
+/// Another path gives the file with this exact path. None if nothing is
+/// selected.
+pub fn select(directory: &Directory, path: &str) -> Option<Output> {
+    if !path.ends_with('/') {
+        return directory.get(path).cloned().map(Output::File);
+    }
+    let selected: Directory = directory
+        .range(path.to_string()..)
+        .take_while(|(p, _)| p.starts_with(path))
+        .map(|(p, content)| (p[path.len()..].to_string(), content.clone()))
+        .collect();
+    if selected.is_empty() {
+        None
+    } else {
+        Some(Output::Directory(selected))
+    }
+}
+
+#[test]
+fn test_select() {
+    let directory: Directory = ["out/lib/a.so", "out/lib/b/c.so", "out/libx", "out/bin"]
+        .iter()
+        .map(|p| (p.to_string(), p.as_bytes().to_vec()))
+        .collect();
+    let lib = match select(&directory, "out/lib/") {
+        Some(Output::Directory(lib)) => lib,
+        s => panic!("Expected a directory, get {:?}", s),
+    };
+    assert_eq!(vec!["a.so", "b/c.so"], lib.keys().collect::<Vec<_>>());
+    assert_eq!(Some(&b"out/lib/a.so".to_vec()), lib.get("a.so"));
+    assert_eq!(None, select(&directory, "doc/"));
+
+    assert_eq!(
+        Some(Output::File(b"out/libx".to_vec())),
+        select(&directory, "out/libx")
+    );
+    assert_eq!(None, select(&directory, "out/lib"));
+    assert_eq!(None, select(&directory, "out/li"));
+}
@@ -1,26 +1,85 @@
-use super::ConfigurationError;
+use super::{lexer::Lexer, ConfigurationError};
+use std::ops::Range;
 
-#[derive(std::cmp::PartialEq, Debug)]
+/// The version of the grammar, selected by the header of the file.
+#[derive(std::cmp::PartialEq, Debug, Clone, Copy, PartialOrd, Eq, Ord)]
 pub enum Version {
+    /// The first grammar, `CAGE-BUILD-0`.
     V0,
+    /// `CAGE-BUILD-1` adds the two chars pipes, the map pipe `*|`, the
+    /// parentheses for pipelines and named inputs, the select operator `/` and
+    /// the keywords `override` and `secret`.
+    V1,
 }
 
 impl Version {
+    /// The newest version, written by `cage migrate`.
+    pub const LATEST: Version = Version::V1;
+
     // Get the version from the file.
     pub fn get(file_content: &str) -> Result<(Version, &str, usize), ConfigurationError> {
         let (h, f, line) =
             Version::get_line(file_content).ok_or(ConfigurationError::VersionNotFound)?;
-        let v = match h {
-            "CAGE-BUILD-0" => Version::V0,
-            _ => Err(ConfigurationError::VersionUnknown(h.to_string()))?,
-        };
+        let h = &file_content[h];
+        let v = Version::from_header(h)
+            .ok_or_else(|| ConfigurationError::VersionUnknown(h.to_string()))?;
 
         Ok((v, f, line))
     }
 
-    /// Get the header, the rest of the file and the line number.
-    fn get_line(file_content: &str) -> Option<(&str, &str, usize)> {
-        let mut line = 1;
+    /// Get the byte range of the header read by [`Version::get`], without the
+    /// comment and the spaces.
+    pub fn span(file_content: &str) -> Option<Range<usize>> {
+        Version::get_line(file_content).map(|(h, _, _)| h)
+    }
+
+    /// Get the version from the header line, without the comment.
+    pub fn from_header(header: &str) -> Option<Version> {
+        match header {
+            "CAGE-BUILD-0" => Some(Version::V0),
+            "CAGE-BUILD-1" => Some(Version::V1),
+            _ => None,
+        }
+    }
+
+    /// The header line of the version.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Version::V0 => "CAGE-BUILD-0",
+            Version::V1 => "CAGE-BUILD-1",
+        }
+    }
+
+    /// The keywords of the grammar, they can not be a variable name.
+    pub fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Version::V0 => &["dir", "file", "tag"],
+            Version::V1 => &["dir", "file", "override", "secret", "tag"],
+        }
+    }
+
+    /// Create the lexer with the rules of the version, for the rest of the file
+    /// after the header, that begins at `line`.
+    pub fn lexer<'a>(&self, rest: &'a str, line: usize) -> Lexer<'a> {
+        Lexer::with_version(rest, line, *self)
+    }
+
+    /// Get the interpreter line of an executable configuration file, like
+    /// `#!/usr/bin/env cage`. It must be the first line, the header is read
+    /// after it.
+    pub fn shebang(file_content: &str) -> Option<&str> {
+        let line = file_content.lines().next()?;
+        line.strip_prefix("#!").map(str::trim)
+    }
+
+    /// Get the range of the header, the rest of the file and the line number.
+    /// The interpreter line is skipped.
+    fn get_line(full_content: &str) -> Option<(Range<usize>, &str, usize)> {
+        let (file_content, mut line) = match Version::shebang(full_content) {
+            Some(_) => (full_content.split_once('\n')?.1, 2),
+            None => (full_content, 1),
+        };
+        let base = full_content.len() - file_content.len();
         let mut comment: bool = false;
         for (i, c) in file_content.char_indices() {
             if c == '\n' {
@@ -42,9 +101,10 @@ impl Version {
                     Some((h, _)) => h,
                     None => h,
                 }
-                .trim();
+                .trim_end();
+                let start = base + i;
 
-                return Some((h, &f[separator + 1..], line + 1));
+                return Some((start..start + h.len(), &f[separator + 1..], line + 1));
             }
         }
 
@@ -90,4 +150,20 @@ config file
     assert_eq!(Version::V0, v);
     assert_eq!(2, l);
     assert_eq!("", f);
+
+    let script = "#!/usr/bin/env cage\nCAGE-BUILD-0\nfile a \"a\"\n";
+    assert_eq!(Some("/usr/bin/env cage"), Version::shebang(script));
+    assert_eq!(
+        (Version::V0, "file a \"a\"\n", 3),
+        Version::get(script).unwrap()
+    );
+    assert_eq!(
+        None,
+        Version::shebang("CAGE-BUILD-0\n#!/usr/bin/env cage\n")
+    );
+    assert_eq!(
+        (Version::V1, "", 4),
+        Version::get("#!/usr/bin/env cage\n\nCAGE-BUILD-1\n").unwrap()
+    );
+    assert!(Version::get("#!/usr/bin/env cage").is_err());
 }
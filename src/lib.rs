@@ -1 +1,11 @@
-mod configuration;
+pub mod api;
+pub mod archive;
+pub mod build;
+pub mod configuration;
+pub mod lock;
+pub mod lsp;
+pub mod serve;
+#[cfg(test)]
+mod temp;
+mod walk;
+mod watch;
@@ -0,0 +1,152 @@
+//! The lock file, with the precise version of each generator. Read and written by cage.
+//!
+//! ```text
+//! CAGE-LOCK-0
+//! https://exemple.com/minifier 1.4.2 sha256:5e2bf57d3f40c4b6df69daf1936cb766f832374b4fc0259a7cbff06e2f70f269
+//! ```
+
+use std::collections::BTreeMap;
+use std::{error::Error, fmt};
+
+/// The header of the lock file.
+const HEADER: &str = "CAGE-LOCK-0";
+
+/// The content of the lock file.
+#[derive(Debug, Default, PartialEq)]
+pub struct Lock(BTreeMap<String, Dependency>);
+
+/// The resolved version of one generator.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dependency {
+    pub version: String,
+    /// The hash of the WASM module, prefixed by the algorithm. Ex: `sha256:5e2b...`.
+    pub hash: String,
+}
+
+/// Error from lock file parsing.
+#[derive(Debug, PartialEq)]
+pub enum LockError {
+    /// The file does not begin with the `CAGE-LOCK-0` header.
+    HeaderNotFound,
+    /// A line without exactly an URL, a version and a hash.
+    InvalidLine(usize),
+    /// An URL is present two times, the line of the second.
+    Duplicate(usize, String),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HeaderNotFound => write!(f, "The lock file header {:?} not found", HEADER),
+            Self::InvalidLine(l) => write!(
+                f,
+                "Invalid lock file at line {}, expected an URL, a version and a hash",
+                l
+            ),
+            Self::Duplicate(l, url) => {
+                write!(
+                    f,
+                    "Duplicate generator {:?} in lock file at line {}",
+                    url, l
+                )
+            }
+        }
+    }
+}
+impl Error for LockError {}
+
+impl Lock {
+    /// Parse the content of a lock file.
+    pub fn parse(content: &str) -> Result<Self, LockError> {
+        let mut lines = content.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(LockError::HeaderNotFound),
+        };
+
+        let mut lock = Lock::default();
+        for (i, line) in lines.filter(|(_, l)| !l.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (url, version, hash) = match fields[..] {
+                [url, version, hash] => (url, version, hash),
+                _ => return Err(LockError::InvalidLine(i)),
+            };
+            if lock.0.contains_key(url) {
+                return Err(LockError::Duplicate(i, url.to_string()));
+            }
+            lock.insert(url, version, hash);
+        }
+
+        Ok(lock)
+    }
+
+    /// Set the version of a generator.
+    pub fn insert(&mut self, url: &str, version: &str, hash: &str) {
+        self.0.insert(
+            url.to_string(),
+            Dependency {
+                version: version.to_string(),
+                hash: hash.to_string(),
+            },
+        );
+    }
+
+    /// Get the version of the generator from its URL.
+    pub fn get(&self, url: &str) -> Option<&Dependency> {
+        self.0.get(url)
+    }
+
+    /// Iter over the generator URL and their versions, sorted by URL.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Dependency)> {
+        self.0.iter().map(|(url, d)| (url.as_str(), d))
+    }
+}
+
+impl fmt::Display for Lock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for (url, d) in self.iter() {
+            writeln!(f, "{} {} {}", url, d.version, d.hash)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_lock() {
+    let content = "CAGE-LOCK-0
+https://exemple.com/minifier 1.4.2 sha256:5e2b
+
+https://exemple.com/bundler 0.1.0 sha256:f270
+";
+    let lock = Lock::parse(content).unwrap();
+    assert_eq!(
+        Some(&Dependency {
+            version: "1.4.2".to_string(),
+            hash: "sha256:5e2b".to_string(),
+        }),
+        lock.get("https://exemple.com/minifier")
+    );
+    assert_eq!(None, lock.get("https://exemple.com/unknown"));
+    assert_eq!(
+        "CAGE-LOCK-0
+https://exemple.com/bundler 0.1.0 sha256:f270
+https://exemple.com/minifier 1.4.2 sha256:5e2b
+",
+        lock.to_string()
+    );
+    assert_eq!(
+        Ok(lock),
+        Lock::parse(&Lock::parse(content).unwrap().to_string())
+    );
+
+    assert_eq!(Err(LockError::HeaderNotFound), Lock::parse(""));
+    assert_eq!(
+        Err(LockError::InvalidLine(2)),
+        Lock::parse("CAGE-LOCK-0\nhttps://exemple.com/minifier 1.4.2\n")
+    );
+    assert_eq!(
+        Err(LockError::Duplicate(3, "https://exemple.com/a".to_string())),
+        Lock::parse("CAGE-LOCK-0\nhttps://exemple.com/a 1 h\nhttps://exemple.com/a 2 h\n")
+    );
+}
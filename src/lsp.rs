@@ -0,0 +1,257 @@
+//! The language server for the configuration files, speak LSP over stdio.
+
+use crate::configuration::{
+    language::{self, CompletionKind},
+    Position,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// Run the language server until the `exit` notification or the end of the input.
+pub fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {
+    let mut documents: HashMap<String, String> = HashMap::new();
+
+    while let Some(message) = read(&mut input)? {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["$"] },
+                },
+                "serverInfo": { "name": "cage", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                documents.insert(uri.to_string(), text.to_string());
+                publish_diagnostics(&mut output, uri, text)?;
+                continue;
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    documents.insert(uri.to_string(), text.to_string());
+                    publish_diagnostics(&mut output, uri, text)?;
+                }
+                continue;
+            }
+            "textDocument/didClose" => {
+                documents.remove(uri);
+                publish_diagnostics(&mut output, uri, "")?;
+                continue;
+            }
+            "textDocument/definition" => {
+                let text = documents.get(uri).map(String::as_str).unwrap_or("");
+                match language::definition(text, from_lsp(text, &params["position"])) {
+                    Some((p, len)) => json!({ "uri": uri, "range": range(text, p, len) }),
+                    None => Value::Null,
+                }
+            }
+            "textDocument/hover" => {
+                let text = documents.get(uri).map(String::as_str).unwrap_or("");
+                match language::hover(text, from_lsp(text, &params["position"])) {
+                    Some(h) => json!({ "contents": { "kind": "markdown", "value": h } }),
+                    None => Value::Null,
+                }
+            }
+            "textDocument/completion" => {
+                let text = documents.get(uri).map(String::as_str).unwrap_or("");
+                let items: Vec<Value> = language::completion(text)
+                    .into_iter()
+                    .map(|c| {
+                        let kind = match c.kind {
+                            CompletionKind::Keyword => 14,
+                            CompletionKind::System => 21,
+                            CompletionKind::Variable => 6,
+                        };
+                        json!({ "label": c.label, "kind": kind })
+                    })
+                    .collect();
+                Value::Array(items)
+            }
+            "shutdown" => Value::Null,
+            "exit" => return Ok(()),
+            _ if message["id"].is_null() => continue,
+            _ => {
+                write(
+                    &mut output,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "id": message["id"],
+                        "error": { "code": -32601, "message": format!("Unknown method {:?}", method) },
+                    }),
+                )?;
+                continue;
+            }
+        };
+
+        if !message["id"].is_null() {
+            write(
+                &mut output,
+                &json!({ "jsonrpc": "2.0", "id": message["id"], "result": result }),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn publish_diagnostics<W: Write>(output: &mut W, uri: &str, text: &str) -> io::Result<()> {
+    let diagnostics: Vec<Value> = language::diagnostics(text)
+        .into_iter()
+        .map(|d| {
+            json!({
+                "range": range(text, d.position, 1),
+                "severity": 1,
+                "source": "cage",
+                "message": d.message,
+            })
+        })
+        .collect();
+    write(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }),
+    )
+}
+
+/// Read one message, return None at the end of the input.
+fn read<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        } else if let Some(l) = line.strip_prefix("Content-Length:") {
+            length = l.trim().parse().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing the Content-Length header",
+        )
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// Convert a LSP position (from zero, in UTF-16 code units) to a [`Position`].
+fn from_lsp(text: &str, position: &Value) -> Position {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let mut units = 0;
+    let column = text
+        .lines()
+        .nth(line)
+        .unwrap_or("")
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= character
+        })
+        .count();
+    Position {
+        line: line + 1,
+        column: column + 1,
+    }
+}
+
+/// Get the LSP range from the position with `len` chars.
+fn range(text: &str, position: Position, len: usize) -> Value {
+    let line = text.lines().nth(position.line - 1).unwrap_or("");
+    let utf16 =
+        |column: usize| -> usize { line.chars().take(column - 1).map(char::len_utf16).sum() };
+    json!({
+        "start": { "line": position.line - 1, "character": utf16(position.column) },
+        "end": { "line": position.line - 1, "character": utf16(position.column + len) },
+    })
+}
+
+#[test]
+fn test_lsp() {
+    let uri = "file:///project/build.cage";
+    let text =
+        "CAGE-BUILD-0\ndir été \"front/\" > $\"https://exemple.com/min\"\ndir $pkg [ été ]\n";
+    let mut input = Vec::new();
+    for m in [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": { "uri": uri, "text": text },
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/definition", "params": {
+            "textDocument": { "uri": uri }, "position": { "line": 2, "character": 12 },
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "textDocument/hover", "params": {
+            "textDocument": { "uri": uri }, "position": { "line": 2, "character": 5 },
+        }}),
+        json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+            "textDocument": { "uri": uri }, "contentChanges": [{ "text": "CAGE-BUILD-0\ndir a [ b ]\n" }],
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "unknown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ]
+    .iter()
+    {
+        write(&mut input, m).unwrap();
+    }
+
+    let mut output = Vec::new();
+    run(&input[..], &mut output).unwrap();
+
+    let mut output = &output[..];
+    let mut next = || read(&mut output).unwrap().unwrap();
+
+    let initialize = next();
+    assert_eq!(json!(1), initialize["id"]);
+    assert_eq!(
+        json!(true),
+        initialize["result"]["capabilities"]["definitionProvider"]
+    );
+    assert_eq!(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {
+            "uri": uri, "diagnostics": [],
+        }}),
+        next()
+    );
+    assert_eq!(
+        json!({ "jsonrpc": "2.0", "id": 2, "result": { "uri": uri, "range": {
+            "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 7 },
+        }}}),
+        next()
+    );
+    assert!(next()["result"]["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .starts_with("`$pkg`"));
+    let diagnostics = next();
+    assert_eq!(
+        json!({ "start": { "line": 1, "character": 8 }, "end": { "line": 1, "character": 9 } }),
+        diagnostics["params"]["diagnostics"][0]["range"]
+    );
+    assert_eq!(json!(-32601), next()["error"]["code"]);
+    assert!(read(&mut output).unwrap().is_none());
+}
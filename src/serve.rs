@@ -0,0 +1,250 @@
+//! A development web server, serve a directory over HTTP on localhost.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::path::{Component, Path, PathBuf};
+use std::{fs, io, thread};
+
+/// Serve the directory `root` at `address` (ex: `localhost:8080`). Never return
+/// except on error when binding the address.
+pub fn serve<A: ToSocketAddrs>(root: &Path, address: A) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        let root = root.to_path_buf();
+        thread::spawn(move || {
+            let reader = match stream.try_clone() {
+                Ok(s) => BufReader::new(s),
+                Err(_) => return,
+            };
+            // The client can close the connection, nothing to do.
+            let _ = handle(&root, reader, stream);
+        });
+    }
+    Ok(())
+}
+
+/// Replace the served directory `root` with the files written by `write`. They
+/// are written into a sibling directory, then swapped in with two renames, so a
+/// request never reads a partly written or removed directory.
+pub fn replace<F>(root: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    let next = root.with_extension("next");
+    let old = root.with_extension("old");
+    remove(&next)?;
+    remove(&old)?;
+    write(&next)?;
+    match fs::rename(root, &old) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    fs::rename(&next, root)?;
+    remove(&old)
+}
+
+/// Remove the file or the directory, if it exists.
+fn remove(path: &Path) -> io::Result<()> {
+    let r = match fs::symlink_metadata(path) {
+        Ok(m) if m.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match r {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Read one request and write the response.
+fn handle<R: BufRead, W: Write>(root: &Path, mut reader: R, mut writer: W) -> io::Result<()> {
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut request = request.split_whitespace();
+    let (method, target) = match (request.next(), request.next()) {
+        (Some(m), Some(t)) => (m, t),
+        _ => return respond(&mut writer, "400 Bad Request", "text/plain", b"Bad Request"),
+    };
+    if method != "GET" && method != "HEAD" {
+        return respond(
+            &mut writer,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed",
+        );
+    }
+
+    let path = target.split(['?', '#']).next().unwrap_or("");
+    let file = match resolve(root, path) {
+        Some(f) => f,
+        None => return respond(&mut writer, "404 Not Found", "text/plain", b"Not Found"),
+    };
+    let body = match fs::read(&file) {
+        Ok(b) => b,
+        Err(_) => return respond(&mut writer, "404 Not Found", "text/plain", b"Not Found"),
+    };
+    let mime = mime_type(&file.to_string_lossy());
+    if method == "HEAD" {
+        write!(
+            writer,
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            mime,
+            body.len()
+        )?;
+        writer.flush()
+    } else {
+        respond(&mut writer, "200 OK", mime, &body)
+    }
+}
+
+fn respond<W: Write>(writer: &mut W, status: &str, mime: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        mime,
+        body.len()
+    )?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+/// Get the file to serve from the URL path. Get the `index.html` for a directory.
+/// Return None if the path go out of the root or if the file does not exist.
+fn resolve(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(url_path)?;
+    let relative = Path::new(decoded.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let mut file = root.join(relative);
+    if file.is_dir() {
+        file.push("index.html");
+    }
+    if file.is_file() {
+        Some(file)
+    } else {
+        None
+    }
+}
+
+/// Decode the `%XX` sequences of the URL path.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let h = [iter.next()?, iter.next()?];
+            let h = std::str::from_utf8(&h).ok()?;
+            bytes.push(u8::from_str_radix(h, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Get the MIME type from the extension of the path.
+pub fn mime_type(path: &str) -> &'static str {
+    let extension = match path.rsplit_once('.') {
+        Some((_, e)) if !e.contains('/') => e.to_ascii_lowercase(),
+        _ => return "application/octet-stream",
+    };
+    match &extension[..] {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[test]
+fn test_serve() {
+    let root = crate::temp::TempDir::new("serve");
+    fs::create_dir_all(root.join("app dir")).unwrap();
+    fs::write(root.join("index.html"), "<h1>Hello</h1>").unwrap();
+    fs::write(root.join("app dir/main.wasm"), b"\0asm").unwrap();
+
+    let request = |r: &str| {
+        let mut response = Vec::new();
+        handle(&root, r.as_bytes(), &mut response).unwrap();
+        String::from_utf8(response).unwrap()
+    };
+
+    assert_eq!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: 14\r\nConnection: close\r\n\r\n<h1>Hello</h1>",
+        request("GET /?reload HTTP/1.1\r\nHost: localhost\r\n\r\n")
+    );
+    assert_eq!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/wasm\r\nContent-Length: 4\r\nConnection: close\r\n\r\n\0asm",
+        request("GET /app%20dir/main.wasm HTTP/1.1\r\n\r\n")
+    );
+    assert_eq!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/wasm\r\nContent-Length: 4\r\nConnection: close\r\n\r\n",
+        request("HEAD /app%20dir/main.wasm HTTP/1.1\r\n\r\n")
+    );
+    assert!(request("GET /../index.html HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404 "));
+    assert!(request("GET /app%20dir/ HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404 "));
+    assert!(request("POST / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405 "));
+    assert!(request("\r\n").starts_with("HTTP/1.1 400 "));
+
+    assert_eq!("text/css; charset=utf-8", mime_type("style.CSS"));
+    assert_eq!("application/octet-stream", mime_type("v1.2/LICENSE"));
+}
+
+#[test]
+fn test_replace() {
+    let dir = crate::temp::TempDir::new("serve-replace");
+    let root = dir.join("site");
+    replace(&root, |d| {
+        fs::create_dir_all(d)?;
+        fs::write(d.join("old.html"), "old")
+    })
+    .unwrap();
+    replace(&root, |d| {
+        fs::create_dir_all(d)?;
+        fs::write(d.join("index.html"), "new")
+    })
+    .unwrap();
+    assert_eq!(
+        vec!["site"],
+        fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!("new", fs::read_to_string(root.join("index.html")).unwrap());
+    assert!(!root.join("old.html").exists());
+
+    // A failed write keeps the served directory.
+    assert!(replace(&root, |_| Err(io::Error::other("failed"))).is_err());
+    assert_eq!("new", fs::read_to_string(root.join("index.html")).unwrap());
+}
@@ -0,0 +1,38 @@
+//! A temporary directory for the tests.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+/// An empty directory in the temporary directory of the system. It is removed
+/// when dropped, also when an assert fails.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create the directory `cage-test-<name>-<pid>`, the name is unique by test.
+    pub fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("cage-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
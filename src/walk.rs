@@ -0,0 +1,120 @@
+//! List the repository files, with the same policy for the links in the build,
+//! the globs, the watch and the archive. A link is followed when its target is
+//! in the repository, else it is an error. A link to a directory that contains
+//! a walked directory is skipped, so a link loop is not walked again.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// List the repository files of `path`, relative to `root`: the file itself,
+/// or the files of the directory, recursively and sorted. The paths are
+/// relative to the repository. A path where `skip` is true is not listed, and
+/// a skipped directory is not walked.
+pub fn files<F>(root: &Path, path: &str, skip: F) -> io::Result<Vec<String>>
+where
+    F: Fn(&str) -> bool,
+{
+    let repository = fs::canonicalize(root)?;
+    let path = path.trim_end_matches('/');
+    if !fs::canonicalize(root.join(path))?.starts_with(&repository) {
+        return Err(out_of_repository(path));
+    }
+    let mut files = Vec::new();
+    walk(root, &repository, path, &skip, &mut Vec::new(), &mut files)?;
+    Ok(files)
+}
+
+/// Walk into `path`, `parents` are the real paths of the walked directories
+/// that contain it.
+fn walk(
+    root: &Path,
+    repository: &Path,
+    path: &str,
+    skip: &dyn Fn(&str) -> bool,
+    parents: &mut Vec<PathBuf>,
+    files: &mut Vec<String>,
+) -> io::Result<()> {
+    let full = root.join(path);
+    let mut metadata = fs::symlink_metadata(&full)?;
+    if metadata.file_type().is_symlink() {
+        let target = fs::canonicalize(&full)?;
+        if !target.starts_with(repository) {
+            return Err(out_of_repository(path));
+        } else if parents.iter().any(|p| p.starts_with(&target)) {
+            return Ok(());
+        }
+        metadata = fs::metadata(&full)?;
+    }
+    if !metadata.is_dir() {
+        files.push(path.to_string());
+        return Ok(());
+    }
+
+    parents.push(fs::canonicalize(&full)?);
+    let mut names = Vec::new();
+    for e in fs::read_dir(&full)? {
+        names.push(e?.file_name().to_string_lossy().to_string());
+    }
+    names.sort_unstable();
+    for name in names {
+        let child = if path.is_empty() {
+            name
+        } else {
+            format!("{}/{}", path, name)
+        };
+        if !skip(&child) {
+            walk(root, repository, &child, skip, parents, files)?;
+        }
+    }
+    parents.pop();
+    Ok(())
+}
+
+fn out_of_repository(path: &str) -> io::Error {
+    io::Error::other(format!(
+        "The path {:?} is a link out of the repository",
+        path
+    ))
+}
+
+#[cfg(unix)]
+#[test]
+fn test_files() {
+    use std::os::unix::fs::symlink;
+
+    let dir = crate::temp::TempDir::new("walk");
+    let root = dir.join("repo");
+    fs::create_dir_all(root.join("front/sub")).unwrap();
+    fs::write(root.join("front/sub/app.js"), "app").unwrap();
+    fs::write(root.join("front/index.html"), "index").unwrap();
+    fs::write(root.join("README.md"), "readme").unwrap();
+    fs::write(dir.join("outside"), "outside").unwrap();
+    symlink("sub", root.join("front/alias")).unwrap();
+    symlink("..", root.join("front/loop")).unwrap();
+    symlink("../README.md", root.join("front/readme")).unwrap();
+
+    assert_eq!(
+        vec![
+            "front/alias/app.js",
+            "front/index.html",
+            "front/readme",
+            "front/sub/app.js",
+        ],
+        files(&root, "front/", |_| false).unwrap()
+    );
+    assert_eq!(
+        vec!["front/index.html", "front/readme"],
+        files(&root, "front", |p| p.ends_with("sub")
+            || p.ends_with("alias"))
+        .unwrap()
+    );
+    assert_eq!(
+        vec!["README.md"],
+        files(&root, "README.md", |_| false).unwrap()
+    );
+
+    symlink("../../outside", root.join("front/host")).unwrap();
+    assert!(files(&root, "front/", |_| false).is_err());
+    assert!(files(&root, "front/host", |_| false).is_err());
+    assert!(files(&root, "front/", |p| p == "front/host").is_ok());
+}
@@ -0,0 +1,110 @@
+//! Detect the modifications of repository files by polling, without dependency
+//! on the system notification API.
+
+use crate::walk;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+use std::{fs, io, path::Path, thread};
+
+/// The modification time and the size of files, by path relative to the repository.
+#[derive(Debug, Default, PartialEq)]
+pub struct Snapshot(BTreeMap<String, (SystemTime, u64)>);
+
+impl Snapshot {
+    /// Take the snapshot of the files and the directories (recursively) `paths`,
+    /// relative to `root`. A not existing path is ignored. The links follow
+    /// the policy of [`walk::files`], like the build.
+    pub fn take<'a, I>(root: &Path, paths: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut s = Snapshot::default();
+        for p in paths {
+            let files = match walk::files(root, p, |_| false) {
+                Ok(files) => files,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for f in files {
+                let m = fs::metadata(root.join(&f))?;
+                s.0.insert(f, (m.modified()?, m.len()));
+            }
+        }
+        Ok(s)
+    }
+
+    /// Get the files created, removed or modified in the `new` snapshot, sorted.
+    pub fn changes<'a>(&'a self, new: &'a Snapshot) -> Vec<&'a str> {
+        let mut changes: Vec<&str> = self
+            .0
+            .iter()
+            .filter(|(p, m)| new.0.get(*p) != Some(m))
+            .chain(new.0.iter().filter(|(p, _)| !self.0.contains_key(*p)))
+            .map(|(p, _)| p.as_str())
+            .collect();
+        changes.sort_unstable();
+        changes
+    }
+}
+
+/// Wait until at least one file of `paths` changes, by checking every `interval`.
+/// Return the new snapshot and the changed files.
+pub fn wait(
+    root: &Path,
+    paths: &[&str],
+    old: &Snapshot,
+    interval: Duration,
+) -> io::Result<(Snapshot, Vec<String>)> {
+    loop {
+        thread::sleep(interval);
+        let new = Snapshot::take(root, paths.iter().copied())?;
+        let changes: Vec<String> = old.changes(&new).into_iter().map(String::from).collect();
+        if !changes.is_empty() {
+            return Ok((new, changes));
+        }
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    let root = crate::temp::TempDir::new("watch");
+    fs::create_dir_all(root.join("front/lib")).unwrap();
+    fs::write(root.join("front/app.js"), "app").unwrap();
+    fs::write(root.join("front/lib/util.js"), "util").unwrap();
+    fs::write(root.join("style.css"), "body {}").unwrap();
+    fs::write(root.join("README.md"), "readme").unwrap();
+
+    let paths = ["front/", "style.css", "missing.txt"];
+    let old = Snapshot::take(&root, paths.iter().copied()).unwrap();
+    assert_eq!(
+        vec!["front/app.js", "front/lib/util.js", "style.css"],
+        old.0.keys().map(String::as_str).collect::<Vec<_>>()
+    );
+
+    fs::write(root.join("front/app.js"), "app modified").unwrap();
+    fs::remove_file(root.join("front/lib/util.js")).unwrap();
+    fs::write(root.join("front/lib/new.js"), "new").unwrap();
+    fs::write(root.join("README.md"), "readme modified").unwrap();
+
+    let (new, changes) = wait(&root, &paths, &old, Duration::from_millis(1)).unwrap();
+    assert_eq!(
+        vec!["front/app.js", "front/lib/new.js", "front/lib/util.js"],
+        changes
+    );
+    assert!(new.changes(&new).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_snapshot_link() {
+    let root = crate::temp::TempDir::new("watch-link");
+    fs::create_dir_all(root.join("front")).unwrap();
+    fs::write(root.join("front/app.js"), "app").unwrap();
+    std::os::unix::fs::symlink("..", root.join("front/loop")).unwrap();
+
+    let snapshot = Snapshot::take(&root, ["front/"].iter().copied()).unwrap();
+    assert_eq!(
+        vec!["front/app.js"],
+        snapshot.0.keys().map(String::as_str).collect::<Vec<_>>()
+    );
+}